@@ -0,0 +1,33 @@
+// conway's game of life, plugged into a `World` as a custom `TransitionFn`, to show how other dynamics can reuse the
+// stepping/rendering machinery. prints a glider as it crawls across a small world.
+// run with: cargo run --example conway
+
+use color_rules_cellular_automata::*;
+
+struct Life;
+
+impl TransitionFn for Life {
+    fn transition(&self, neighborhood:&[u32; 9], center:u32) -> u32 {
+        // symbol 1 is alive and 0 is dead. the neighborhood includes the cell itself, so take it back out:
+        let live_neighbors = neighborhood.iter().sum::<u32>() - center;
+        match (center, live_neighbors) {
+            (1, 2) | (_, 3) => 1,
+            _ => 0,
+        }
+    }
+}
+
+fn main() {
+    // the rules generated from the seed are ignored once a custom transition is set, so any seed will do:
+    let mut world = World::new(8, 2, 1.0, 0);
+    world.data = vec![0; 64];
+    for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        world.data[y*8 + x] = 1;
+    }
+    world.set_transition_fn(Life);
+
+    for generation in 0..8 {
+        println!("generation {}:\n{}", generation, world.to_ascii());
+        world.step();
+    }
+}
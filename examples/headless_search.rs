@@ -0,0 +1,47 @@
+// searches random seeds without a window, and saves a gif of the first one that looks interesting.
+// run with: cargo run --release --example headless_search
+
+use color_rules_cellular_automata::*;
+
+fn main() {
+    // the same generation parameters the main program starts with:
+    let config = WorldConfig { size: 128, symbol_count: 5, avg_symbols_per_rule: 2.0, transition_mode: TransitionMode::Rules, repulsion: false, edge_matching: false };
+    let params = NoveltySearchParams { sample_frame_count: 400, min_end_cell_diff: 25, change_window: 5, change_window_gap: 0, burn_in_frames: 0 };
+    let max_frames = 1000;
+
+    for _ in 0..50 {
+        let seed = rand::random::<u64>();
+
+        // the seed only decides the rules and colors, so keep the (randomized) starting grid around for the replay below:
+        let mut world = World::from_config(&config, seed);
+        world.randomize();
+        let initial_data = world.data.clone();
+
+        // steps until nothing changes any more (or `max_frames` is reached), scoring novelty along the way:
+        let summary = run_headless(&mut world, seed, &params, max_frames);
+        let score = NoveltyFitness.score(&summary);
+        println!("seed: {}  score: {}  active frames: {}  unique: {}", seed, score, summary.active_frames, summary.unique_frame_count);
+        if score <= params.min_end_cell_diff as f64 {
+            continue;
+        }
+
+        // replay the run from the same starting grid, this time keeping every frame:
+        let mut world = World::from_config(&config, seed);
+        world.data = initial_data;
+        let mut frames = vec![];
+        for _ in 0..summary.active_frames {
+            world.step();
+            frames.push(world.data.clone());
+        }
+
+        println!("boundary energy: {}  complexity: {:.3}", world.boundary_energy(), world.complexity_score());
+
+        // `make_gif_from_frames` writes into ./gifs/ (at 2x2 pixels per cell here)
+        std::fs::create_dir_all("gifs").unwrap();
+        make_gif_from_frames(&frames, &world.symbol_to_color, &format!("headless_search--seed_{}", seed), Some(seed), 2);
+        println!("saved gifs/headless_search--seed_{}.gif", seed);
+        return;
+    }
+
+    println!("nothing interesting this time, try again");
+}
@@ -0,0 +1,49 @@
+// times stepping a sparse world (a few sparks on an empty background) with and without `quiescent_symbol`, and checks
+// that both end up with the same grid.
+// run with: cargo run --release --example quiescent_benchmark
+
+use color_rules_cellular_automata::*;
+use std::time::Instant;
+
+fn main() {
+    let size = 512;
+    let frame_count = 200;
+    let fresh_world_count = 20;
+    // sparks (1) burn (2) out into ash (3), which clears back to the background (0) and can re-ignite it. none of these
+    // fire on an all-background neighborhood, so those cells can be skipped:
+    let rules = vec![
+        WorldRule { symbols_needed: vec![0, 1], output_symbol: 2, requires_motion: false, min_count: 1 },
+        WorldRule { symbols_needed: vec![2], output_symbol: 3, requires_motion: false, min_count: 2 },
+        WorldRule { symbols_needed: vec![3], output_symbol: 0, requires_motion: false, min_count: 1 },
+        WorldRule { symbols_needed: vec![0, 3], output_symbol: 1, requires_motion: true, min_count: 1 },
+    ];
+    let mut template = World::new(size, 4, 1.0, 0);
+    template.set_rules(rules).unwrap();
+    template.data = (0..size*size).map(|i| if i.wrapping_mul(2654435761) % 997 == 0 { 1 } else { 0 }).collect();
+
+    // every cell of a fresh world is pending (as it is after `randomize`, `set_rules`, etc), which is where the change
+    // flags can't help, so time the first few steps of a batch of fresh worlds as well as one long run:
+    let mut results = vec![];
+    for quiescent_symbol in [None, Some(0)].iter() {
+        let start = Instant::now();
+        for _ in 0..fresh_world_count {
+            let mut world = template.clone();
+            world.quiescent_symbol = *quiescent_symbol;
+            for _ in 0..3 {
+                world.step();
+            }
+        }
+        let fresh_elapsed = start.elapsed();
+
+        let mut world = template.clone();
+        world.quiescent_symbol = *quiescent_symbol;
+        let start = Instant::now();
+        for _ in 0..frame_count {
+            world.step();
+        }
+        let elapsed = start.elapsed();
+        println!("quiescent symbol: {:?}  fresh worlds: {:?} per step  long run: {:?} per step", quiescent_symbol, fresh_elapsed / (fresh_world_count * 3), elapsed / frame_count);
+        results.push(world.data);
+    }
+    assert_eq!(results[0], results[1], "skipping quiescent cells changed the result");
+}
@@ -0,0 +1,32 @@
+// compares where worlds end up with wang-tile-style edge matching (`--wang-tiles`) against the default rule generation,
+// using the same seeds (and so the same starting grids) for both.
+// run with: cargo run --release --example wang_tiles
+
+use color_rules_cellular_automata::*;
+
+fn main() {
+    let seed_count = 20;
+    let frame_count = 300;
+    let default_config = WorldConfig { size: 64, symbol_count: 5, avg_symbols_per_rule: 2.0, transition_mode: TransitionMode::Rules, repulsion: false, edge_matching: false };
+    let edge_matching_config = WorldConfig { edge_matching: true, ..default_config.clone() };
+
+    for (name, config) in [("default", &default_config), ("wang tiles", &edge_matching_config)].iter() {
+        let (mut symmetry, mut energy, mut complexity) = (0.0, 0.0, 0.0);
+        for seed in 0..seed_count {
+            let mut world = World::from_config(config, seed);
+            world.randomize_from_seed(seed);
+            for _ in 0..frame_count {
+                world.step();
+                if world.is_static() {
+                    break;
+                }
+            }
+            let score = world.symmetry_score();
+            symmetry += score.horizontal.max(score.vertical).max(score.rotational);
+            energy += world.boundary_energy() as f32 / world.data.len() as f32;
+            complexity += world.complexity_score();
+        }
+        let n = seed_count as f32;
+        println!("{:>10}:  symmetry: {:.3}  boundary energy per cell: {:.3}  complexity: {:.3}", name, symmetry / n, energy / n, complexity / n as f64);
+    }
+}
@@ -0,0 +1,4486 @@
+use std::mem;
+use rand::thread_rng;
+use rand::Rng;
+#[cfg(feature="parallel")]
+use rayon::prelude::*;
+#[cfg(feature="config-file")]
+use serde::{Serialize, Deserialize};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldRule {
+    pub symbols_needed: Vec<u32>,
+    pub output_symbol: u32,
+    pub requires_motion: bool, // only fires if something in the neighborhood changed last step
+    pub min_count: u32, // each needed symbol has to fill at least this many cells of the neighborhood (1 means just present)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn contains(&self, pos:(u32, u32)) -> bool {
+        let (x, y) = pos;
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SymmetryScore {
+    pub horizontal: f32, // left-right mirror
+    pub vertical: f32, // top-bottom mirror
+    pub rotational: f32, // 180 degrees
+}
+
+pub struct FrameView {
+    pub index: usize,
+    pub hash: u64,
+    pub changed_count: u32,
+    pub histogram: Vec<u32>,
+}
+
+// a custom local rule, for experimenting with dynamics other than color rules while reusing the stepping, rendering and
+// search machinery. `neighborhood` is the 3x3 block around the cell in row-major order (so `neighborhood[4]` is the
+// cell itself, i.e. `center`), wrapped around the edges of the world.
+pub trait TransitionFn: Send + Sync {
+    fn transition(&self, neighborhood:&[u32; 9], center:u32) -> u32;
+}
+
+// the usual color-rule matching as a `TransitionFn` (first matching rule wins, otherwise the cell keeps its value). a
+// `World` uses its own (faster) copy of this logic unless it's given a custom transition, so this is mainly a starting
+// point for custom rules. motion-gated rules never fire here, since a transition only sees the current neighborhood.
+pub struct RuleTransition {
+    pub rules: Vec<WorldRule>,
+}
+
+impl TransitionFn for RuleTransition {
+    fn transition(&self, neighborhood:&[u32; 9], center:u32) -> u32 {
+        let count = |symbol:u32| neighborhood.iter().filter(|v| **v == symbol).count() as u32;
+        self.rules.iter()
+            .filter(|rule| !rule.requires_motion)
+            .find(|rule| rule.symbols_needed.iter().all(|symbol| count(*symbol) >= rule.min_count.max(1)))
+            .map_or(center, |rule| rule.output_symbol)
+    }
+}
+
+// a cell that changed in a step, and why
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChangeEvent {
+    pub x: u32,
+    pub y: u32,
+    pub old_symbol: u32,
+    pub new_symbol: u32,
+    pub rule_index: Option<usize>, // index into the step's rule phase (None if it came from the majority transition)
+}
+
+// what the next step does to one cell, and why (see `World::explain_cell`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellExplanation {
+    pub frame: u32, // the frame the step starts from
+    pub x: u32,
+    pub y: u32,
+    pub phase: usize, // which rule phase the step uses
+    pub neighborhood: Vec<u32>, // row by row, with the cell itself in the middle
+    pub motion: bool, // whether anything in the neighborhood changed last step (for motion-gated rules)
+    pub frozen: bool, // frozen cells keep their symbol without the rules being checked
+    pub old_symbol: u32,
+    pub new_symbol: u32,
+    pub rule_index: Option<usize>, // index into the phase's rules (None if no rule fired)
+    pub rule: Option<WorldRule>,
+}
+
+impl std::fmt::Display for CellExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "frame {}  cell ({}, {})  phase {}  motion: {}", self.frame, self.x, self.y, self.phase, if self.motion { "yes" } else { "no" })?;
+        let side = (self.neighborhood.len() as f64).sqrt() as usize;
+        for row in self.neighborhood.chunks(side) {
+            let row: Vec<String> = row.iter().map(|s| format!("{:>2}", s)).collect();
+            writeln!(f, "  {}", row.join(" "))?;
+        }
+        match (&self.rule, self.rule_index) {
+            (Some(rule), Some(i)) => {
+                let needed: Vec<String> = rule.symbols_needed.iter().map(|s| s.to_string()).collect();
+                write!(f, "  {} -> {} by rule {} (needs {}", self.old_symbol, self.new_symbol, i, needed.join(" "))?;
+                if rule.min_count != 1 {
+                    write!(f, ", at least {} of each", rule.min_count)?;
+                }
+                if rule.requires_motion {
+                    write!(f, ", with motion")?;
+                }
+                write!(f, ")")
+            }
+            _ if self.frozen => write!(f, "  {} stays {} (frozen)", self.old_symbol, self.new_symbol),
+            _ if self.old_symbol == self.new_symbol => write!(f, "  {} stays {} (no rule fired)", self.old_symbol, self.new_symbol),
+            _ => write!(f, "  {} -> {} (no rule fired, so by the transition mode)", self.old_symbol, self.new_symbol),
+        }
+    }
+}
+
+impl CellExplanation {
+    // the same as the `Display` text, as a single line of json
+    pub fn to_json(&self) -> String {
+        let list = |symbols:&[u32]| symbols.iter().map(|s| s.to_string()).collect::<Vec<String>>().join(",");
+        let optional = |v:Option<String>| v.unwrap_or_else(|| "null".to_string());
+        let rule = self.rule.as_ref().map(|rule| format!("{{\"symbols_needed\":[{}],\"output_symbol\":{},\"requires_motion\":{},\"min_count\":{}}}", list(&rule.symbols_needed), rule.output_symbol, rule.requires_motion, rule.min_count));
+        format!("{{\"frame\":{},\"x\":{},\"y\":{},\"phase\":{},\"neighborhood\":[{}],\"motion\":{},\"frozen\":{},\"old_symbol\":{},\"new_symbol\":{},\"rule_index\":{},\"rule\":{}}}",
+            self.frame, self.x, self.y, self.phase, list(&self.neighborhood), self.motion, self.frozen, self.old_symbol, self.new_symbol, optional(self.rule_index.map(|i| i.to_string())), optional(rule))
+    }
+}
+
+struct FrameIter {
+    world: World,
+    index: usize,
+}
+
+impl Iterator for FrameIter {
+    type Item = FrameView;
+
+    fn next(&mut self) -> Option<FrameView> {
+        self.world.step();
+        let view = FrameView {
+            index: self.index,
+            hash: calculate_vec_hash(&self.world.data),
+            changed_count: self.world.cell_changed_flags.iter().filter(|v| **v).count() as u32,
+            histogram: self.world.histogram(),
+        };
+        self.index += 1;
+        Some(view)
+    }
+}
+
+#[derive(Debug)]
+pub enum WorldError {
+    Image(image::ImageError),
+    Io(std::io::Error),
+    Palette(String),
+    Grid(String),
+    Cell(String),
+    Rule(String),
+    Rle(String),
+}
+
+impl std::fmt::Display for WorldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WorldError::Image(e) => write!(f, "image error: {}", e),
+            WorldError::Io(e) => write!(f, "io error: {}", e),
+            WorldError::Palette(e) => write!(f, "palette error: {}", e),
+            WorldError::Grid(e) => write!(f, "grid error: {}", e),
+            WorldError::Cell(e) => write!(f, "cell error: {}", e),
+            WorldError::Rule(e) => write!(f, "rule error: {}", e),
+            WorldError::Rle(e) => write!(f, "rle world error: {}", e),
+        }
+    }
+}
+
+impl From<image::ImageError> for WorldError {
+    fn from(e: image::ImageError) -> WorldError {
+        WorldError::Image(e)
+    }
+}
+
+impl From<std::io::Error> for WorldError {
+    fn from(e: std::io::Error) -> WorldError {
+        WorldError::Io(e)
+    }
+}
+
+// the generative parameters of a world (everything other than the seed)
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature="config-file", derive(Serialize, Deserialize), serde(default))]
+pub struct WorldConfig {
+    pub size: u32,
+    pub symbol_count: u32,
+    pub avg_symbols_per_rule: f32,
+    pub transition_mode: TransitionMode,
+    pub repulsion: bool, // see `World::add_repulsion_rules`
+    pub edge_matching: bool, // see `wang_edge_colors`
+}
+
+// what the main program starts with
+impl Default for WorldConfig {
+    fn default() -> WorldConfig {
+        WorldConfig { size: 512, symbol_count: 5, avg_symbols_per_rule: 4.0, transition_mode: TransitionMode::Rules, repulsion: false, edge_matching: false }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature="config-file", derive(Serialize, Deserialize))]
+pub enum TransitionMode {
+    Rules, // first matching rule wins, otherwise the cell keeps its value
+    Majority, // the cell becomes the most common symbol in its neighborhood (rules are ignored)
+    RulesThenMajority, // majority is used instead of keeping the value when no rule matches
+}
+
+#[derive(Clone)]
+pub struct World {
+    pub size: u32,
+    pub data: Vec<u32>,
+    prev_data: Vec<u32>,
+    pub cell_changed_flags: Vec<bool>,
+    neighborhood_changed_flags: Vec<bool>,
+    prev_neighborhood_changed_flags: Vec<bool>,
+    neighborhood_radius: u32,
+    pub symbol_count: u32,
+    pub symbol_to_color: Vec<(u8, u8, u8)>,
+    rule_phases: Vec<Vec<WorldRule>>, // step `n` uses rule set `n % rule_phases.len()` (usually there's just one)
+    rule_mask: Option<Vec<bool>>, // rules whose entry is false are skipped (None means they're all enabled)
+    pub locked_symbols: std::collections::HashSet<u32>, // cells holding one of these never change (i.e. they act as walls)
+    // a cell that changes *into* one of these never changes again (unlike locking, cells that start out holding one
+    // aren't affected). with a sparse spark this gives crystal-like accretion.
+    pub frozen_symbols: std::collections::HashSet<u32>,
+    frozen_cells: Vec<bool>,
+    pub transition_mode: TransitionMode,
+    // a "background" symbol: cells whose whole neighborhood holds it are skipped without checking the rules, which
+    // for sparse worlds saves most of the work of a step. only used when no rule fires on such a neighborhood.
+    pub quiescent_symbol: Option<u32>,
+    // when the world is bigger than the buffer it's drawn into, average the colors of the cells under each pixel (true)
+    // or draw one pixel per cell and crop whatever doesn't fit (false)
+    pub antialias: bool,
+    // when set, `draw_to_buffer` multiplies the colors of cells that didn't change in the last step by this, so that the
+    // active regions (gliders, wave fronts, oscillators) stand out against a faded background
+    pub change_highlight: Option<f32>,
+    // a toroidal (dx, dy) roll applied when drawing, so the wraparound seam can be moved away from whatever's interesting.
+    // the cell at (x, y) is drawn at (x + dx, y + dy). the simulation doesn't see it.
+    pub view_offset: (i32, i32),
+    pub frame: u32, // number of steps taken so far
+    // a per-cell brightness that the rules nudge up or down (see `enable_intensity`), shading the otherwise flat symbols.
+    // `None` (the default) draws every cell at full brightness.
+    intensity: Option<Vec<u8>>,
+    intensity_deltas: Vec<i8>, // how much each rule (by index) changes the intensity of the cells it changes
+    last_changed: Option<Vec<u32>>, // the frame each cell last changed in (only tracked when enabled, since it costs a pass per step)
+    change_events: Option<Vec<ChangeEvent>>, // the last step's changes (only recorded when enabled, since it allocates)
+    custom_transition: Option<std::sync::Arc<dyn TransitionFn>>, // replaces the rules (and transition mode) when set
+}
+
+impl World {
+
+    pub fn new(world_size:u32, symbol_count:u32, avg_symbols_per_rule:f32, seed:u64) -> World {
+        World::new_with_phases(world_size, symbol_count, avg_symbols_per_rule, seed, 1)
+    }
+
+    // like `new`, but generates `phase_count` independent rule sets which are cycled through one per step (like
+    // alternating transition tables). the first phase is the same rule set that `new` would generate for the seed.
+    pub fn new_with_phases(world_size:u32, symbol_count:u32, avg_symbols_per_rule:f32, seed:u64, phase_count:u32) -> World {
+        World::generate(world_size, symbol_count, avg_symbols_per_rule, seed, phase_count, false)
+    }
+
+    // `edge_matching` biases each rule's output towards symbols whose edges match the edges of the symbols the rule
+    // needs (see `wang_edge_colors`), for tiling/self-assembly-like dynamics. without it, the rules are the same as ever.
+    fn generate(world_size:u32, symbol_count:u32, avg_symbols_per_rule:f32, seed:u64, phase_count:u32, edge_matching:bool) -> World {
+        assert!( (world_size as f32).log(2.0) % 1.0 == 0.0, "World size must be a power of 2.");
+        assert!( avg_symbols_per_rule > 0.0, "Average symbols per rule must be positive.");
+        assert!( phase_count > 0, "There must be at least one rule phase.");
+
+        // includes end
+        use rand::{SeedableRng, rngs::StdRng};
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut random = |start:u32, end:u32| -> u32 {
+            let r: f32 = rng.gen::<f32>();
+            start + (r * (end-start) as f32).round() as u32
+        };
+
+        let mut symbol_to_color = Vec::new();
+        for _i in 0..symbol_count {
+            let r = random(0, 255) as u8;
+            let g = random(0, 255) as u8;
+            let b = random(0, 255) as u8;
+            symbol_to_color.push((r, g, b));
+        }
+
+        // (indent means equals)
+        //
+        // > probabiliy of at least one rule match at a position
+        //   > one
+        //   > minus
+        //   > the probability of none of the rules matching at a position
+        //     > the product of the probabilities of each rule not matching
+        //       > the probability of a random rule rule not matching (i.e. at least one symbol doesn't match)
+        //         > one
+        //         > minus
+        //         > the probability of all the symbols of the rule *matching*
+        //           > the probability of a particular symbol existing in a particular neighborhood
+        //             > the probability that a symbol is in at least one of the 9 cells
+        //               > one
+        //               > minus
+        //               > the probability that the symbol is in NONE of the squares
+        //                 > the probabiliy that a cell doesn't contain a particular symbol
+        //                   > 1 - (1 / number_of_symbols)
+        //                 > to the power of
+        //                 > nine
+        //           > to the power of
+        //           > the average number of symbols in a rule
+        //       > to the power of
+        //       > the number of rules
+        let neighborhood_radius: u32 = 1;
+        let neighborhood_cell_count = (2*neighborhood_radius + 1).pow(2);
+
+        let rule_count = generated_rule_count(symbol_count, avg_symbols_per_rule, neighborhood_cell_count).expect("Too many rules would be needed to cover the neighborhoods (lower the average symbols per rule).");
+
+        // #[cfg(feature="interactive")] {
+        //     println!("world_size: {}", world_size);
+        //     println!("symbol_count: {}", symbol_count);
+        //     println!("avg_symbols_per_rule: {}", avg_symbols_per_rule);
+        //     println!("rule_count: {}", rule_count);
+        //     println!("prob_match: {}", prob_match);
+        // }
+
+        let add_symbol_chance = avg_symbols_per_rule / symbol_count as f32;
+        assert!(add_symbol_chance < 1.0, "Average symbols per rule must be less than the symbol count.");
+
+        let edge_colors = if edge_matching { Some(wang_edge_colors(symbol_count, seed)) } else { None };
+
+        let mut rule_phases = Vec::new();
+        for _phase in 0..phase_count {
+            let mut world_rules = Vec::new();
+            for _i in 0..rule_count {
+                let mut symbols_needed = Vec::new();
+                for symbol in 0..symbol_count {
+                    if (random(0, 1000) as f32) < add_symbol_chance*1000.0 { 
+                        symbols_needed.push(symbol);
+                    }
+                }
+                if symbols_needed.is_empty() {
+                    symbols_needed.push(random(0, symbol_count-1));
+                }
+                let output_symbol = match &edge_colors {
+                    // every symbol keeps a weight of at least 1 (so any output is still possible), plus 4 for each
+                    // needed symbol that it can sit next to. this takes exactly one draw, like the default, so the rest
+                    // of the rules (and the colors) are the same as they'd be without edge matching.
+                    Some(edge_colors) => {
+                        let weights: Vec<u32> = (0..symbol_count).map(|output| {
+                            let compatible_count = symbols_needed.iter().filter(|s| wang_compatible(&edge_colors[output as usize], &edge_colors[**s as usize])).count() as u32;
+                            symbols_needed.len() as u32 + 4*compatible_count
+                        }).collect();
+                        let mut roll = random(0, weights.iter().sum::<u32>() - 1);
+                        weights.iter().position(|w| if roll < *w { true } else { roll -= w; false }).unwrap() as u32
+                    }
+                    None => random(0, symbol_count-1),
+                };
+                world_rules.push(WorldRule { symbols_needed, output_symbol, requires_motion: false, min_count: 1 });
+            }
+            assert!( !world_rules.is_empty() );
+            rule_phases.push(world_rules);
+        }
+
+        World {
+            size: world_size,
+            data: vec![0; world_size.pow(2) as usize],
+            prev_data: vec![0; world_size.pow(2) as usize],
+            cell_changed_flags: vec![true; world_size.pow(2) as usize],
+            neighborhood_changed_flags: vec![true; world_size.pow(2) as usize],
+            prev_neighborhood_changed_flags: vec![true; world_size.pow(2) as usize],
+            neighborhood_radius,
+            symbol_count,
+            symbol_to_color,
+            rule_phases,
+            rule_mask: None,
+            locked_symbols: std::collections::HashSet::new(),
+            frozen_symbols: std::collections::HashSet::new(),
+            frozen_cells: vec![false; world_size.pow(2) as usize],
+            transition_mode: TransitionMode::Rules,
+            quiescent_symbol: None,
+            antialias: true,
+            change_highlight: None,
+            view_offset: (0, 0),
+            frame: 0,
+            intensity: None,
+            intensity_deltas: vec![],
+            last_changed: None,
+            change_events: None,
+            custom_transition: None,
+        }
+    }
+
+    pub fn from_config(config:&WorldConfig, seed:u64) -> World {
+        let mut world = World::generate(config.size, config.symbol_count, config.avg_symbols_per_rule, seed, 1, config.edge_matching);
+        world.transition_mode = config.transition_mode;
+        if config.repulsion {
+            world.add_repulsion_rules(seed);
+        }
+        world
+    }
+
+    // a world whose grid starts out as a sparse spark instead of dense noise: symbol 0 everywhere except for about
+    // `init_density` of the cells, which get one of the other symbols at random (see `spark_weights`). growth and wave
+    // dynamics often only show up from starts like this. `seed` decides the rules and colors as usual, and `init_seed`
+    // the grid.
+    pub fn spark(config:&WorldConfig, seed:u64, init_density:f32, init_seed:u64) -> World {
+        let mut world = World::from_config(config, seed);
+        world.randomize_weighted(&spark_weights(config.symbol_count, init_density), init_seed);
+        world
+    }
+
+    // gives every symbol a top-precedence rule that turns it into some other (seeded random) symbol wherever it fills
+    // all but one cell of the neighborhood. the rules have to be fixed before the grid exists, so rather than guessing
+    // which symbol will end up dominant, each one gets a rule - only the dominant one's will fire much.
+    // this is a direct test of the "fighting downhill-ness" idea: nothing can monopolize a region for long.
+    fn add_repulsion_rules(&mut self, seed:u64) {
+        use rand::{SeedableRng, rngs::StdRng};
+        let mut rng = StdRng::seed_from_u64(seed);
+        let min_count = (2*self.neighborhood_radius + 1).pow(2) - 1;
+        let repulsion_rules: Vec<WorldRule> = (0..self.symbol_count).map(|symbol| {
+            let output_symbol = (symbol + rng.gen_range(1, self.symbol_count)) % self.symbol_count;
+            WorldRule { symbols_needed: vec![symbol], output_symbol, requires_motion: false, min_count }
+        }).collect();
+        for rules in self.rule_phases.iter_mut() {
+            rules.splice(0..0, repulsion_rules.iter().cloned());
+        }
+    }
+
+    // an out-of-range symbol would otherwise only blow up much later, when it's used to index `symbol_to_color` while
+    // rendering, far away from whatever wrote it
+    pub fn set(&mut self, pos:(u32, u32), value:u32) -> Result<(), WorldError> {
+        let (x, y) = pos;
+        if x >= self.size || y >= self.size {
+            return Err(WorldError::Cell(format!("({}, {}) is outside of the {}x{} world", x, y, self.size, self.size)));
+        }
+        if value >= self.symbol_count {
+            return Err(WorldError::Cell(format!("symbol {} at ({}, {}) is out of range (the world has {} symbols)", value, x, y, self.symbol_count)));
+        }
+        self.data[(y * self.size + x) as usize] = value;
+        self.mark_neighborhood_changed(x, y);
+        Ok(())
+    }
+
+    // makes the next step re-evaluate every cell whose neighborhood includes (x, y), after it was changed from outside
+    // of `step` (otherwise the sparse stepping could skip them, and the change would never spread)
+    fn mark_neighborhood_changed(&mut self, x:u32, y:u32) {
+        let r = self.neighborhood_radius as i32;
+        let (x, y) = (x as i32, y as i32);
+        for ny in (y - r)..(y + r + 1) {
+            for nx in (x - r)..(x + r + 1) {
+                self.neighborhood_changed_flags[(wrap(ny, self.size)*self.size + wrap(nx, self.size)) as usize] = true;
+            }
+        }
+    }
+
+    // changes `fraction` of the cells (picked from `seed`, so the same call always hits the same cells) to a different
+    // random symbol, and makes sure the next step looks at everything around them. for robustness experiments: let a
+    // pattern settle, perturb a clone of it, and see whether it recovers. returns the indices of the changed cells.
+    pub fn apply_perturbation(&mut self, fraction:f32, seed:u64) -> Vec<usize> {
+        use rand::{SeedableRng, rngs::StdRng};
+        assert!((0.0..=1.0).contains(&fraction), "Perturbation fraction must be between 0 and 1.");
+        if self.symbol_count < 2 {
+            return vec![];
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let count = (fraction * self.data.len() as f32).round() as usize;
+        let mut cells = rand::seq::index::sample(&mut rng, self.data.len(), count).into_vec();
+        cells.sort_unstable();
+        for i in cells.iter() {
+            self.data[*i] = (self.data[*i] + rng.gen_range(1, self.symbol_count)) % self.symbol_count;
+            self.mark_neighborhood_changed(*i as u32 % self.size, *i as u32 / self.size);
+        }
+        cells
+    }
+
+    pub fn _set(&mut self, pos:(u32, u32), value:u32) {
+        self.set(pos, value).unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    pub fn step(&mut self) {
+        self.step_within(None, cfg!(feature="parallel"));
+    }
+
+    // only applies transitions to cells inside `rect` (neighbors are still read from outside it). everything else
+    // stays frozen, but remembers that its neighborhood changed so that a later full `step` picks it up.
+    pub fn step_region(&mut self, rect:Rect) {
+        self.step_within(Some(rect), cfg!(feature="parallel"));
+    }
+
+    // `parallel` spreads the cells over rayon's threadpool (it's only ever true when the `parallel` feature is on). the
+    // sequential path runs exactly the same per-cell logic with plain iterators.
+    fn step_within(&mut self, region:Option<Rect>, parallel:bool) {
+
+        mem::swap(&mut self.data, &mut self.prev_data);
+
+        let phase = self.frame as usize % self.rule_phases.len();
+        self.frame += 1;
+
+        self.cell_changed_flags.iter_mut().for_each(|v| *v = false);
+
+        let world_size = self.size;
+        let radius = self.neighborhood_radius;
+        let params = TransitionParams {
+            world_size,
+            radius,
+            rule_phases: &self.rule_phases,
+            phase,
+            rule_mask: self.rule_mask.as_deref(),
+            locked_symbols: &self.locked_symbols,
+            mode: self.transition_mode,
+            custom_transition: self.custom_transition.as_ref().map(|t| t.as_ref()),
+        };
+        // the rules, phase and mask can all change between steps, so whether an all-quiescent neighborhood stays that way
+        // is checked each time (it's a single transition on a tiny grid):
+        let quiescent_symbol = self.quiescent_symbol.filter(|symbol| quiescent_is_stable(*symbol, &params));
+        // motion-gated rules make the transition depend on last step's change flags too, so a cell whose
+        // neighborhood *stopped* changing still needs one more evaluation:
+        let has_motion_rules = self.rule_phases[phase].iter().any(|rule| rule.requires_motion);
+        // and with several phases a cell can change even if nothing around it did (the next phase's rules may match
+        // where the last phase's didn't), so every cell has to be evaluated:
+        let sparse = self.rule_phases.len() == 1;
+
+        let cell_changed_flags = &mut self.cell_changed_flags;
+        let neighborhood_changed_flags = &mut self.neighborhood_changed_flags;
+        let prev_neighborhood_changed_flags = &mut self.prev_neighborhood_changed_flags;
+        
+        let frozen_symbols = &self.frozen_symbols;
+        let frozen_cells = &mut self.frozen_cells;
+        
+        let prev_data = &self.prev_data;
+        let data = &mut self.data;
+
+        let update_cell = |(i, ((((cell, cell_changed_flag), neighborhood_changed_flag), prev_neighborhood_changed_flag), frozen)): CellUpdate| {
+            let x = i as u32 % world_size;
+            let y = (i as u32 - x) / world_size;
+            if *frozen {
+                *cell = prev_data[i];
+                return;
+            }
+            if let Some(rect) = region {
+                if !rect.contains((x, y)) {
+                    *cell = prev_data[i]; // may be stale otherwise, since it's not necessarily static
+                    return;
+                }
+            }
+            if sparse && !*neighborhood_changed_flag && !(has_motion_rules && *prev_neighborhood_changed_flag) {
+                return;
+            }
+            if let Some(symbol) = quiescent_symbol {
+                if neighborhood_is_uniform(prev_data, world_size, radius.max(1), (x, y), symbol) {
+                    *cell = prev_data[i]; // it may have only just become quiescent, so `data` can be stale here
+                    return;
+                }
+            }
+            let current_value = prev_data[i]; // remember, `prev_data` is "current" value because we did a mem:swap at the start of `step()`
+            let (next_value, _) = compute_transition(prev_data, &params, (x, y), *neighborhood_changed_flag);
+            *cell = next_value;
+            *cell_changed_flag = next_value != current_value;
+            if *cell_changed_flag && frozen_symbols.contains(&next_value) {
+                *frozen = true;
+            }
+        };
+
+        if parallel {
+            #[cfg(feature="parallel")]
+            data.par_iter_mut()
+            .zip(cell_changed_flags.par_iter_mut())
+            .zip(neighborhood_changed_flags.par_iter()) // <-- don't need iter_mut here.
+            .zip(prev_neighborhood_changed_flags.par_iter())
+            .zip(frozen_cells.par_iter_mut())
+            .enumerate()
+            .for_each(update_cell);
+        } else {
+            data.iter_mut()
+            .zip(cell_changed_flags.iter_mut())
+            .zip(neighborhood_changed_flags.iter())
+            .zip(prev_neighborhood_changed_flags.iter())
+            .zip(frozen_cells.iter_mut())
+            .enumerate()
+            .for_each(update_cell);
+        }
+
+        // the transitions are deterministic, so rather than slowing down the pass above the rule that fired is worked out
+        // again afterwards (and only for the cells that changed):
+        if let Some(change_events) = self.change_events.as_mut() {
+            change_events.clear();
+            for (i, _) in cell_changed_flags.iter().enumerate().filter(|(_, changed)| **changed) {
+                let x = i as u32 % world_size;
+                let y = i as u32 / world_size;
+                let (new_symbol, rule_index) = compute_transition(prev_data, &params, (x, y), neighborhood_changed_flags[i]);
+                change_events.push(ChangeEvent { x, y, old_symbol: prev_data[i], new_symbol, rule_index });
+            }
+        }
+
+        // same for the intensity:
+        if let Some(intensity) = self.intensity.as_mut() {
+            let intensity_deltas = &self.intensity_deltas;
+            for (i, _) in cell_changed_flags.iter().enumerate().filter(|(_, changed)| **changed) {
+                let (_, rule_index) = compute_transition(prev_data, &params, (i as u32 % world_size, i as u32 / world_size), neighborhood_changed_flags[i]);
+                if let Some(delta) = rule_index.and_then(|r| intensity_deltas.get(r)) {
+                    intensity[i] = (i16::from(intensity[i]) + i16::from(*delta)).clamp(0, 255) as u8;
+                }
+            }
+        }
+
+        // the erosion must use the same radius as the transition, otherwise cells whose neighborhood
+        // changed would be skipped on the next step.
+        mem::swap(neighborhood_changed_flags, prev_neighborhood_changed_flags);
+        erode_change_flags(cell_changed_flags, neighborhood_changed_flags, world_size, radius);
+
+        if let Some(last_changed) = self.last_changed.as_mut() {
+            let frame = self.frame;
+            last_changed.iter_mut().zip(cell_changed_flags.iter()).for_each(|(last_changed, changed)| {
+                if *changed {
+                    *last_changed = frame;
+                }
+            });
+        }
+
+        // frozen cells haven't been evaluated since their neighborhood changed, so they're still pending:
+        if let Some(rect) = region {
+            neighborhood_changed_flags.iter_mut().zip(prev_neighborhood_changed_flags.iter()).enumerate().for_each(|(i, (flag, prev_flag))| {
+                let x = i as u32 % world_size;
+                let y = (i as u32 - x) / world_size;
+                if !rect.contains((x, y)) {
+                    *flag |= *prev_flag;
+                }
+            });
+        }
+
+    }
+
+    // what the next `step` will do to the cell at `pos` and why: its neighborhood, the rule that fires (if any) and the
+    // symbol it ends up with. the world isn't changed, so call it before each `step` to follow a cell along.
+    pub fn explain_cell(&self, pos:(u32, u32)) -> CellExplanation {
+        assert!(pos.0 < self.size && pos.1 < self.size, "The cell must be inside the world.");
+        let world_size = self.size;
+        let i = (pos.1 * world_size + pos.0) as usize;
+        let phase = self.frame as usize % self.rule_phases.len();
+        let params = TransitionParams {
+            world_size,
+            radius: self.neighborhood_radius,
+            rule_phases: &self.rule_phases,
+            phase,
+            rule_mask: self.rule_mask.as_deref(),
+            locked_symbols: &self.locked_symbols,
+            mode: self.transition_mode,
+            custom_transition: self.custom_transition.as_ref().map(|t| t.as_ref()),
+        };
+        let r = self.neighborhood_radius as i32;
+        let neighborhood = (-r..r+1).flat_map(|dy| (-r..r+1).map(move |dx| (dx, dy))).map(|(dx, dy)| {
+            self.data[(wrap(pos.1 as i32 + dy, world_size) * world_size + wrap(pos.0 as i32 + dx, world_size)) as usize]
+        }).collect();
+        // `step` skips cells whose neighborhood didn't change (or is all quiescent), but only because this would give
+        // the same result for them:
+        let motion = self.neighborhood_changed_flags[i];
+        let frozen = self.frozen_cells[i];
+        let (new_symbol, rule_index) = if frozen { (self.data[i], None) } else { compute_transition(&self.data, &params, pos, motion) };
+        CellExplanation {
+            frame: self.frame,
+            x: pos.0,
+            y: pos.1,
+            phase,
+            neighborhood,
+            motion,
+            frozen,
+            old_symbol: self.data[i],
+            new_symbol,
+            rule_index,
+            rule: rule_index.map(|r| self.rule_phases[phase][r].clone()),
+        }
+    }
+
+    // rule order matters (first match wins), so this gives a variant of the same rule content. deterministic per `seed`.
+    pub fn shuffle_rules(&mut self, seed:u64) {
+        use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+        let mut rng = StdRng::seed_from_u64(seed);
+        for rules in self.rule_phases.iter_mut() {
+            rules.shuffle(&mut rng);
+        }
+        self.mark_all_changed();
+    }
+
+    // the rules in order of precedence (for worlds with several phases, the first phase's)
+    pub fn rules(&self) -> &[WorldRule] {
+        &self.rule_phases[0]
+    }
+
+    // how big the rule set is, across all phases: the number of rules and the average number of symbols each one needs.
+    // `new` keeps doubling the rule count until the rules cover enough neighborhoods, so this varies a lot between seeds.
+    pub fn rule_complexity(&self) -> (usize, f32) {
+        let rule_count = self.rule_phases.iter().map(|rules| rules.len()).sum::<usize>();
+        let needed_count = self.rule_phases.iter().flatten().map(|rule| rule.symbols_needed.len()).sum::<usize>();
+        (rule_count, needed_count as f32 / rule_count.max(1) as f32)
+    }
+
+    // replaces the rules returned by `rules`. the rule mask is cleared, since it was for the old rules, and every cell is
+    // re-evaluated on the next step. worlds with several rule phases are refused, since the phases would no longer line
+    // up with each other.
+    pub fn set_rules(&mut self, rules:Vec<WorldRule>) -> Result<(), WorldError> {
+        if self.rule_phases.len() > 1 {
+            return Err(WorldError::Rule(format!("the world has {} rule phases, but set_rules only replaces one", self.rule_phases.len())));
+        }
+        self.check_rules(&rules)?;
+        self.rule_phases[0] = rules;
+        self.rule_mask = None;
+        self.mark_all_changed();
+        Ok(())
+    }
+
+    fn check_rules(&self, rules:&[WorldRule]) -> Result<(), WorldError> {
+        if rules.is_empty() {
+            return Err(WorldError::Rule("there must be at least one rule".to_string()));
+        }
+        let neighborhood_cell_count = (2*self.neighborhood_radius + 1).pow(2);
+        for (i, rule) in rules.iter().enumerate() {
+            if rule.symbols_needed.is_empty() {
+                return Err(WorldError::Rule(format!("rule {} doesn't need any symbols", i)));
+            }
+            if let Some(symbol) = rule.symbols_needed.iter().chain(std::iter::once(&rule.output_symbol)).find(|s| **s >= self.symbol_count) {
+                return Err(WorldError::Rule(format!("rule {} uses symbol {}, but the world has {} symbols", i, symbol, self.symbol_count)));
+            }
+            if rule.min_count == 0 || rule.min_count > neighborhood_cell_count {
+                return Err(WorldError::Rule(format!("rule {} has a min_count of {}, which must be between 1 and {}", i, rule.min_count, neighborhood_cell_count)));
+            }
+        }
+        Ok(())
+    }
+
+    // steps with `transition` instead of the rules from now on (locked and frozen symbols still apply). the returned
+    // values must be valid symbols for this world.
+    pub fn set_transition_fn<T: TransitionFn + 'static>(&mut self, transition:T) {
+        assert_eq!(self.neighborhood_radius, 1, "Custom transitions only support a neighborhood radius of 1.");
+        self.custom_transition = Some(std::sync::Arc::new(transition));
+        self.mark_all_changed();
+    }
+
+    // disables the rules whose entry in `mask` is false without removing them, so their indices and precedence are kept
+    // for when they're re-enabled (with an all-true mask). the mask applies to every rule phase.
+    pub fn apply_rule_mask(&mut self, mask:&[bool]) {
+        for rules in self.rule_phases.iter() {
+            assert_eq!(mask.len(), rules.len(), "The rule mask must have one entry per rule.");
+        }
+        self.rule_mask = Some(mask.to_vec());
+        self.mark_all_changed();
+    }
+
+    /// which symbols are present in the neighborhood of `(x, y)` (within the neighborhood radius, wrapping around the
+    /// edges like `step` does), as a bitmask with bit `n` set if symbol `n` is there. only for worlds with at most 64
+    /// symbols.
+    ///
+    /// ```
+    /// use color_rules_cellular_automata::World;
+    /// let mut world = World::new(4, 3, 1.0, 1);
+    /// world.data = vec![
+    ///     0, 0, 0, 0,
+    ///     0, 0, 0, 0,
+    ///     0, 0, 0, 1,
+    ///     0, 0, 0, 2,
+    /// ];
+    /// assert_eq!(world.neighborhood_mask(1, 1), 0b001);
+    /// assert_eq!(world.neighborhood_mask(3, 2), 0b111);
+    /// assert_eq!(world.neighborhood_mask(0, 0), 0b101); // (3, 3) is a neighbor, since the world wraps around
+    /// ```
+    pub fn neighborhood_mask(&self, x:u32, y:u32) -> u64 {
+        assert!(self.symbol_count <= 64, "Neighborhood masks only fit worlds with up to 64 symbols.");
+        let r = self.neighborhood_radius as i32;
+        let mut mask = 0u64;
+        for ny in (y as i32 - r)..(y as i32 + r + 1) {
+            for nx in (x as i32 - r)..(x as i32 + r + 1) {
+                let i = wrap(ny, self.size)*self.size + wrap(nx, self.size);
+                mask |= 1 << self.data[i as usize];
+            }
+        }
+        mask
+    }
+
+    // how many cells have each distinct neighborhood (as a `neighborhood_mask`) right now. see `NeighborhoodHistogram`
+    // for adding these up over a run.
+    pub fn neighborhood_histogram(&self) -> std::collections::HashMap<u64, u64> {
+        let mut histogram = std::collections::HashMap::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                *histogram.entry(self.neighborhood_mask(x, y)).or_insert(0) += 1;
+            }
+        }
+        histogram
+    }
+
+    // a (size / factor) square grid where each cell is the most common symbol in the corresponding factor x factor block
+    // (ties go to the lowest symbol), e.g. for a quick preview of a big world without stepping a separate small one
+    pub fn downsample(&self, factor:u32) -> Vec<u32> {
+        assert!(factor > 0 && self.size % factor == 0, "The downsampling factor must divide the world size.");
+        let out_size = self.size / factor;
+        let mut counts = vec![0u32; self.symbol_count as usize];
+        let mut out = Vec::with_capacity((out_size * out_size) as usize);
+        for by in 0..out_size {
+            for bx in 0..out_size {
+                counts.iter_mut().for_each(|c| *c = 0);
+                for y in by*factor..(by+1)*factor {
+                    for x in bx*factor..(bx+1)*factor {
+                        counts[self.data[(y*self.size + x) as usize] as usize] += 1;
+                    }
+                }
+                let max_count = *counts.iter().max().unwrap();
+                out.push(counts.iter().position(|c| *c == max_count).unwrap() as u32);
+            }
+        }
+        out
+    }
+
+    // a hash of the cells inside `rect` (and its shape), so that activity in one part of the world can be told apart
+    // from another (e.g. a small oscillator in an otherwise dead world vs. a noisy corner). it uses `StableHasher`, so
+    // the same contents give the same hash on any build.
+    pub fn hash_region(&self, rect:Rect) -> u64 {
+        assert!(rect.x + rect.width <= self.size && rect.y + rect.height <= self.size, "The region must be inside the world.");
+        let mut hasher = StableHasher::new();
+        hasher.write_u32(rect.width);
+        hasher.write_u32(rect.height);
+        for y in rect.y..rect.y + rect.height {
+            let row_start = (y * self.size + rect.x) as usize;
+            self.data[row_start..row_start + rect.width as usize].iter().for_each(|v| hasher.write_u32(*v));
+        }
+        hasher.finish()
+    }
+
+    // where the cells that aren't the most common (background) symbol are centered. on a torus a plain average is
+    // meaningless (a pattern straddling the edge would be "centered" in the middle of the world), so each axis is treated
+    // as a circle and the angles are averaged instead. returns (0, 0) for a grid with nothing but background.
+    pub fn center_of_mass(&self) -> (u32, u32) {
+        let histogram = self.histogram();
+        let background = histogram.iter().position(|c| c == histogram.iter().max().unwrap()).unwrap() as u32;
+        let foreground = || self.data.iter().enumerate().filter(|(_, v)| **v != background).map(|(i, _)| i as u32);
+        let to_coordinate = |mean:Option<f64>| mean.map_or(0, |m| m.round() as u32 % self.size);
+        (to_coordinate(circular_mean(foreground().map(|i| i % self.size), self.size)), to_coordinate(circular_mean(foreground().map(|i| i / self.size), self.size)))
+    }
+
+    // a hash of the grid (with `StableHasher`, so it's the same on any build), but first shifted (wrapping around) so
+    // that its center of mass is at the origin. a pattern that moves across the world (e.g. a glider) then hashes the
+    // same every time it comes back to the same shape, so its frames repeat with its period instead of all looking
+    // unique.
+    pub fn canonical_hash(&self) -> u64 {
+        let (cx, cy) = self.center_of_mass();
+        let mut hasher = StableHasher::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                hasher.write_u32(self.data[(((y + cy) % self.size) * self.size + (x + cx) % self.size) as usize]);
+            }
+        }
+        hasher.finish()
+    }
+
+    // one identifier for everything that decides what this world looks like from here on: its size, palette, rules (and
+    // how they're applied, including which phase is next), grid and intensities. two worlds with the same checksum
+    // render the same frames, so it works as a key for caching rendered runs on disk. unlike the other hashes it's
+    // stable across platforms and rust versions (see `StableHasher`). a custom transition can't be hashed, so it isn't
+    // covered.
+    pub fn checksum(&self) -> u64 {
+        let mut s = StableHasher::new();
+        s.write_u32(self.size);
+        s.write_u32(self.symbol_count);
+        s.write_u32(self.neighborhood_radius);
+        s.write_u32(match self.transition_mode {
+            TransitionMode::Rules => 0,
+            TransitionMode::Majority => 1,
+            TransitionMode::RulesThenMajority => 2,
+        });
+        s.write_len(self.symbol_to_color.len());
+        for (r, g, b) in self.symbol_to_color.iter() {
+            s.write_bytes(&[*r, *g, *b]);
+        }
+        s.write_len(self.rule_phases.len());
+        for rules in self.rule_phases.iter() {
+            s.write_len(rules.len());
+            for (i, rule) in rules.iter().enumerate() {
+                s.write_len(rule.symbols_needed.len());
+                rule.symbols_needed.iter().for_each(|symbol| s.write_u32(*symbol));
+                s.write_u32(rule.output_symbol);
+                s.write_u32(rule.min_count);
+                s.write_bytes(&[rule.requires_motion as u8, self.rule_mask.as_ref().map_or(true, |mask| mask[i]) as u8]);
+            }
+        }
+        if self.rule_phases.len() > 1 {
+            s.write_len(self.frame as usize % self.rule_phases.len());
+        }
+        for symbols in [&self.locked_symbols, &self.frozen_symbols].iter() {
+            let mut symbols: Vec<u32> = symbols.iter().cloned().collect();
+            symbols.sort_unstable();
+            s.write_len(symbols.len());
+            symbols.iter().for_each(|symbol| s.write_u32(*symbol));
+        }
+        match self.intensity.as_ref() {
+            Some(intensity) => {
+                s.write_bytes(&[1]);
+                s.write_bytes(&self.intensity_deltas.iter().map(|d| *d as u8).collect::<Vec<u8>>());
+                s.write_bytes(intensity);
+            }
+            None => s.write_bytes(&[0]),
+        }
+        s.write_len(self.data.len());
+        self.data.iter().for_each(|v| s.write_u32(*v));
+        s.finish()
+    }
+
+    // a graphviz graph of the rules: one node per symbol (in its color) and an edge from each symbol a rule needs to
+    // the rule's output, labeled with the rule's index (prefixed by the phase when there's more than one). feedback
+    // loops and "food chains" between symbols are much easier to spot this way. render with e.g. `dot -Tpng`.
+    pub fn rules_to_dot(&self) -> String {
+        let mut dot = String::from("digraph rules {\n");
+        for (symbol, (r, g, b)) in self.symbol_to_color.iter().enumerate() {
+            dot.push_str(&format!("    {} [style=filled, fillcolor=\"#{:02x}{:02x}{:02x}\"];\n", symbol, r, g, b));
+        }
+        for (phase, rules) in self.rule_phases.iter().enumerate() {
+            for (i, rule) in rules.iter().enumerate() {
+                let label = if self.rule_phases.len() > 1 { format!("{}.{}", phase, i) } else { i.to_string() };
+                for symbol in rule.symbols_needed.iter() {
+                    dot.push_str(&format!("    {} -> {} [label=\"{}\"];\n", symbol, rule.output_symbol, label));
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    // makes the next step re-evaluate every cell (e.g. after the grid or rules were changed from outside of `step`)
+    fn mark_all_changed(&mut self) {
+        self.neighborhood_changed_flags.iter_mut().for_each(|v| *v = true);
+        self.prev_neighborhood_changed_flags.iter_mut().for_each(|v| *v = true);
+    }
+
+    // gives every cell an intensity (starting at 255, i.e. full brightness) that `draw_to_buffer` scales its color by.
+    // whenever rule `i` changes a cell, `rule_deltas[i]` is added to the cell's intensity (clamped to 0..=255). rules
+    // of other phases are matched up by index too. calling this again resets the intensities.
+    pub fn enable_intensity(&mut self, rule_deltas:Vec<i8>) -> Result<(), WorldError> {
+        if rule_deltas.len() != self.rules().len() {
+            return Err(WorldError::Rule(format!("got {} intensity deltas for {} rules", rule_deltas.len(), self.rules().len())));
+        }
+        self.intensity = Some(vec![255; self.data.len()]);
+        self.intensity_deltas = rule_deltas;
+        Ok(())
+    }
+
+    pub fn intensity(&self) -> Option<&[u8]> {
+        self.intensity.as_deref()
+    }
+
+    // start recording the frame each cell last changed in (e.g. for `draw_trails_to_buffer`)
+    pub fn track_last_changed(&mut self) {
+        if self.last_changed.is_none() {
+            self.last_changed = Some(vec![self.frame; self.data.len()]);
+        }
+    }
+
+    // whether the last step left every cell as it was, i.e. stepping further won't change anything (unless the world
+    // has several rule phases, where a later phase may still match)
+    pub fn is_static(&self) -> bool {
+        !self.cell_changed_flags.iter().any(|v| *v)
+    }
+
+    // whether anything within the transition radius of (x, y) changed in the last step, i.e. whether the next step
+    // will re-evaluate that cell. renderers can use this (or `changed_bounds`) to skip redrawing static regions the
+    // same way `step` skips computing them.
+    pub fn neighbors_changed(&self, x:u32, y:u32) -> bool {
+        self.neighborhood_changed_flags[(y * self.size + x) as usize]
+    }
+
+    // the smallest rectangle around every cell the last step changed (None if nothing did). it doesn't wrap around the
+    // edges, so changes on opposite sides of the world give a rectangle that spans between them.
+    pub fn changed_bounds(&self) -> Option<Rect> {
+        let mut bounds: Option<(u32, u32, u32, u32)> = None; // (min x, min y, max x, max y)
+        for (i, _) in self.cell_changed_flags.iter().enumerate().filter(|(_, changed)| **changed) {
+            let x = i as u32 % self.size;
+            let y = i as u32 / self.size;
+            bounds = Some(match bounds {
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                None => (x, y, x, y),
+            });
+        }
+        bounds.map(|(x0, y0, x1, y1)| Rect { x: x0, y: y0, width: x1 - x0 + 1, height: y1 - y0 + 1 })
+    }
+
+    // start recording which cells each step changes, to what, and by which rule (see `change_events`)
+    pub fn track_change_events(&mut self) {
+        if self.change_events.is_none() {
+            self.change_events = Some(Vec::new());
+        }
+    }
+
+    // the changes made by the last step (empty unless `track_change_events` was called before it)
+    pub fn change_events(&self) -> &[ChangeEvent] {
+        self.change_events.as_ref().map_or(&[], |events| events.as_slice())
+    }
+
+    pub fn randomize(&mut self) {
+        self.randomize_with(&mut thread_rng());
+    }
+
+    // like `randomize`, but the same seed always gives the same grid, so that a seed alone is enough to reproduce a run
+    pub fn randomize_from_seed(&mut self, seed:u64) {
+        use rand::{SeedableRng, rngs::StdRng};
+        self.randomize_with(&mut StdRng::seed_from_u64(seed));
+    }
+
+    // fills the grid from `seed`, with each symbol turning up in proportion to its weight (which don't need to add up
+    // to 1, but there has to be one per symbol)
+    pub fn randomize_weighted(&mut self, weights:&[f32], seed:u64) {
+        use rand::{SeedableRng, rngs::StdRng, distributions::{Distribution, WeightedIndex}};
+        assert_eq!(weights.len(), self.symbol_count as usize, "There must be one weight per symbol.");
+        let distribution = WeightedIndex::new(weights).expect("The weights must be non-negative, and not all 0.");
+        let mut rng = StdRng::seed_from_u64(seed);
+        self.data.iter_mut().for_each(|v| *v = distribution.sample(&mut rng) as u32);
+        self.mark_all_changed();
+    }
+
+    fn randomize_with<R: Rng>(&mut self, rng:&mut R) {
+        for i in 0..self.data.len() {
+            let r:f32 = rng.gen();
+            self.data[i] = (r * self.symbol_count as f32).floor() as u32;
+        }
+    }
+
+    // restores a grid saved with `write_grid` (e.g. the initial grid of an exported animation), which has to be the same
+    // size as this world and only use its symbols
+    pub fn load_grid<P: AsRef<std::path::Path>>(&mut self, path:P) -> Result<(), WorldError> {
+        let text = std::fs::read_to_string(path)?;
+        let mut lines = text.lines();
+        let size = lines.next().and_then(|l| l.trim().parse::<u32>().ok()).ok_or_else(|| WorldError::Grid("missing world size".to_string()))?;
+        if size != self.size {
+            return Err(WorldError::Grid(format!("grid is {0}x{0} but the world is {1}x{1}", size, self.size)));
+        }
+        let runs = lines.map(|l| {
+            let mut parts = l.split_whitespace().map(|p| p.parse::<u32>());
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(Ok(symbol)), Some(Ok(run_length)), None) if symbol < self.symbol_count => Ok((symbol, run_length)),
+                _ => Err(WorldError::Grid(format!("invalid run: {}", l))),
+            }
+        }).collect::<Result<Vec<(u32, u32)>, WorldError>>()?;
+        let data = rle_decode(&runs);
+        if data.len() != self.data.len() {
+            return Err(WorldError::Grid(format!("runs cover {} cells but the world has {}", data.len(), self.data.len())));
+        }
+        self.data = data;
+        self.mark_all_changed();
+        Ok(())
+    }
+
+    // everything needed to carry on from exactly this frame, as text (unlike `to_rle`, which is just the grid). a header line (format version, size, symbol count,
+    // neighborhood radius, transition mode, frame number and palette), then the rules (`rule <needed symbols> -> <output>`
+    // plus `min=<n>`, `motion` and `off` where they apply), one `phase` line before each phase's rules, any locked and
+    // frozen symbols, and finally the grid and last step's changes as runs of `<symbol>*<length>`:
+    //
+    //     rle-world 1 size=4 symbols=2 radius=1 mode=rules frame=7 palette=#1a2b3c,#ffeedd
+    //     phase
+    //     rule 0 1 -> 1
+    //     rule 1 -> 0 min=5 motion
+    //     grid 0*6 1*3 0*7
+    //     changed 0*6 1*3 0*7
+    //
+    // custom transitions and the optional tracking (trails, change events, intensity) aren't included.
+    pub fn to_rle_text(&self) -> String {
+        let mode = match self.transition_mode {
+            TransitionMode::Rules => "rules",
+            TransitionMode::Majority => "majority",
+            TransitionMode::RulesThenMajority => "rules-then-majority",
+        };
+        let palette: Vec<String> = self.symbol_to_color.iter().map(|(r, g, b)| format!("#{:02x}{:02x}{:02x}", r, g, b)).collect();
+        let mut text = format!("rle-world {} size={} symbols={} radius={} mode={} frame={} palette={}\n", RLE_WORLD_VERSION, self.size, self.symbol_count, self.neighborhood_radius, mode, self.frame, palette.join(","));
+        let join = |symbols:&mut dyn Iterator<Item = &u32>| symbols.map(|s| s.to_string()).collect::<Vec<String>>().join(" ");
+        for (phase, rules) in self.rule_phases.iter().enumerate() {
+            text.push_str("phase\n");
+            for (i, rule) in rules.iter().enumerate() {
+                text.push_str(&format!("rule {} -> {}", join(&mut rule.symbols_needed.iter()), rule.output_symbol));
+                if rule.min_count != 1 {
+                    text.push_str(&format!(" min={}", rule.min_count));
+                }
+                if rule.requires_motion {
+                    text.push_str(" motion");
+                }
+                if phase == 0 && self.rule_mask.as_ref().is_some_and(|mask| !mask[i]) {
+                    text.push_str(" off");
+                }
+                text.push('\n');
+            }
+        }
+        for (name, symbols) in [("locked", &self.locked_symbols), ("frozen", &self.frozen_symbols)].iter() {
+            if !symbols.is_empty() {
+                let mut symbols: Vec<u32> = symbols.iter().cloned().collect();
+                symbols.sort_unstable();
+                text.push_str(&format!("{} {}\n", name, join(&mut symbols.iter())));
+            }
+        }
+        let runs_line = |name:&str, data:&[u32]| {
+            let runs: Vec<String> = rle_encode(data).iter().map(|(symbol, run_length)| format!("{}*{}", symbol, run_length)).collect();
+            format!("{} {}\n", name, runs.join(" "))
+        };
+        let flags = |flags:&[bool]| flags.iter().map(|f| *f as u32).collect::<Vec<u32>>();
+        text.push_str(&runs_line("grid", &self.data));
+        text.push_str(&runs_line("changed", &flags(&self.cell_changed_flags)));
+        if self.frozen_cells.iter().any(|f| *f) {
+            text.push_str(&runs_line("frozen-cells", &flags(&self.frozen_cells)));
+        }
+        text
+    }
+
+    // the inverse of `to_rle_text`. stepping the result gives exactly the same frames as stepping the saved world would have.
+    pub fn from_rle_text(text:&str) -> Result<World, WorldError> {
+        let err = |message:String| WorldError::Rle(message);
+        let mut lines = text.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+
+        let header = lines.next().ok_or_else(|| err("the file is empty".to_string()))?;
+        let mut header_parts = header.split_whitespace();
+        if header_parts.next() != Some("rle-world") {
+            return Err(err("missing the `rle-world` header".to_string()));
+        }
+        let version = header_parts.next().and_then(|v| v.parse::<u32>().ok()).ok_or_else(|| err("missing the format version".to_string()))?;
+        if version != RLE_WORLD_VERSION {
+            return Err(err(format!("version {} isn't supported (this build reads version {})", version, RLE_WORLD_VERSION)));
+        }
+        let fields: std::collections::HashMap<&str, &str> = header_parts.filter_map(|p| {
+            let i = p.find('=')?;
+            Some((&p[..i], &p[i+1..]))
+        }).collect();
+        let field = |name:&str| fields.get(name).cloned().ok_or_else(|| err(format!("the header is missing `{}`", name)));
+        let number = |name:&str| field(name).and_then(|v| v.parse::<u32>().map_err(|_| err(format!("invalid {}: {}", name, v))));
+        let size = number("size")?;
+        let symbol_count = number("symbols")?;
+        if size == 0 || !size.is_power_of_two() {
+            return Err(err(format!("the size ({}) must be a power of 2", size)));
+        }
+        if u64::from(size) * u64::from(size) > MAX_RLE_WORLD_CELLS {
+            return Err(err(format!("the size ({}) is too big, since files can have at most {} cells", size, MAX_RLE_WORLD_CELLS)));
+        }
+        let cell_count = (size * size) as usize;
+        if symbol_count == 0 {
+            return Err(err("there must be at least one symbol".to_string()));
+        }
+        let radius = number("radius")?;
+        // (written as a division so that a huge radius can't overflow `2*radius+1`)
+        if radius == 0 || radius > (size - 1) / 2 {
+            return Err(err(format!("the radius ({}) must be at least 1, and its neighborhood ({}x{}) no bigger than the world", radius, radius as u64 * 2 + 1, radius as u64 * 2 + 1)));
+        }
+        // (checked before making the world, which is sized by the symbol count)
+        let palette = field("palette")?.split(',').map(|c| {
+            let hex = c.trim_start_matches('#');
+            let channel = |i:usize| hex.get(i..i+2).and_then(|c| u8::from_str_radix(c, 16).ok());
+            match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok((r, g, b)),
+                _ => Err(err(format!("invalid color: {}", c))),
+            }
+        }).collect::<Result<Vec<_>, _>>()?;
+        if palette.len() != symbol_count as usize {
+            return Err(err(format!("the palette has {} colors for {} symbols", palette.len(), symbol_count)));
+        }
+        let mut world = World::new(size, symbol_count, 0.5, 0);
+        world.symbol_to_color = palette;
+        world.neighborhood_radius = radius;
+        world.frame = number("frame")?;
+        world.transition_mode = match field("mode")? {
+            "rules" => TransitionMode::Rules,
+            "majority" => TransitionMode::Majority,
+            "rules-then-majority" => TransitionMode::RulesThenMajority,
+            mode => return Err(err(format!("unknown transition mode: {}", mode))),
+        };
+
+        let symbols = |s:&str| s.split_whitespace().map(|v| v.parse::<u32>().map_err(|_| err(format!("invalid symbol: {}", v)))).collect::<Result<Vec<u32>, _>>();
+        let runs = |name:&str, s:&str, limit:u32| {
+            let runs = s.split_whitespace().map(|run| {
+                let mut parts = run.split('*').map(|p| p.parse::<u32>());
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(Ok(symbol)), Some(Ok(run_length)), None) if symbol < limit => Ok((symbol, run_length)),
+                    _ => Err(err(format!("invalid run: {}", run))),
+                }
+            }).collect::<Result<Vec<(u32, u32)>, _>>()?;
+            // (added up before decoding, so that a huge run is rejected rather than allocated)
+            let covered = runs.iter().map(|(_, run_length)| u64::from(*run_length)).sum::<u64>();
+            if covered != cell_count as u64 {
+                return Err(err(format!("the {} runs cover {} cells but the world has {}", name, covered, cell_count)));
+            }
+            Ok(rle_decode(&runs))
+        };
+        let mut rule_phases: Vec<Vec<WorldRule>> = vec![];
+        let mut rule_mask = vec![];
+        let (mut data, mut changed, mut frozen_cells) = (None, None, None);
+        for line in lines {
+            let (keyword, rest) = match line.find(' ') {
+                Some(i) => (&line[..i], &line[i+1..]),
+                None => (line, ""),
+            };
+            match keyword {
+                "phase" => rule_phases.push(vec![]),
+                "rule" => {
+                    if rule_phases.is_empty() {
+                        return Err(err("a rule comes before the first `phase`".to_string()));
+                    }
+                    let arrow = rest.find("->").ok_or_else(|| err(format!("invalid rule: {}", line)))?;
+                    let mut after = rest[arrow+2..].split_whitespace();
+                    let output_symbol = after.next().and_then(|v| v.parse::<u32>().ok()).ok_or_else(|| err(format!("invalid rule: {}", line)))?;
+                    let mut rule = WorldRule { symbols_needed: symbols(&rest[..arrow])?, output_symbol, requires_motion: false, min_count: 1 };
+                    let mut enabled = true;
+                    for option in after {
+                        match option {
+                            "motion" => rule.requires_motion = true,
+                            "off" => enabled = false,
+                            _ if option.starts_with("min=") => rule.min_count = option[4..].parse::<u32>().map_err(|_| err(format!("invalid rule: {}", line)))?,
+                            _ => return Err(err(format!("invalid rule: {}", line))),
+                        }
+                    }
+                    // the mask applies to every phase by index, so it's only written (and read) for the first one:
+                    if rule_phases.len() == 1 {
+                        rule_mask.push(enabled);
+                    } else if !enabled {
+                        return Err(err(format!("only rules in the first phase can be `off`: {}", line)));
+                    }
+                    rule_phases.last_mut().unwrap().push(rule);
+                }
+                "locked" => world.locked_symbols = symbols(rest)?.into_iter().collect(),
+                "frozen" => world.frozen_symbols = symbols(rest)?.into_iter().collect(),
+                "grid" => data = Some(runs("grid", rest, symbol_count)?),
+                "changed" => changed = Some(runs("changed", rest, 2)?),
+                "frozen-cells" => frozen_cells = Some(runs("frozen-cells", rest, 2)?),
+                _ => return Err(err(format!("unexpected line: {}", line))),
+            }
+        }
+
+        if rule_phases.is_empty() {
+            return Err(err("there are no rules".to_string()));
+        }
+        for rules in rule_phases.iter() {
+            world.check_rules(rules)?;
+        }
+        let masked = rule_mask.iter().any(|enabled| !*enabled);
+        if masked && rule_phases.iter().any(|rules| rules.len() != rule_mask.len()) {
+            return Err(err("rules can only be `off` when every phase has the same number of rules".to_string()));
+        }
+        world.rule_phases = rule_phases;
+        world.rule_mask = if masked { Some(rule_mask) } else { None };
+        if world.locked_symbols.iter().chain(world.frozen_symbols.iter()).any(|s| *s >= symbol_count) {
+            return Err(err("a locked or frozen symbol is out of range".to_string()));
+        }
+
+        let data = data.ok_or_else(|| err("missing the grid".to_string()))?;
+        world.prev_data = data.clone(); // so cells that aren't re-evaluated on the next step keep their value
+        world.data = data;
+        let changed = changed.unwrap_or_else(|| vec![1; cell_count]);
+        world.cell_changed_flags = changed.iter().map(|v| *v == 1).collect();
+        erode_change_flags(&world.cell_changed_flags, &mut world.neighborhood_changed_flags, size, world.neighborhood_radius);
+        // this is only used to give cells one more look after their neighborhood stops changing, which is harmless for
+        // cells that already had it:
+        world.prev_neighborhood_changed_flags.iter_mut().for_each(|v| *v = true);
+        if let Some(frozen_cells) = frozen_cells {
+            world.frozen_cells = frozen_cells.iter().map(|v| *v == 1).collect();
+        }
+        Ok(world)
+    }
+
+    pub fn save_rle<P: AsRef<std::path::Path>>(&self, path:P) -> Result<(), WorldError> {
+        std::fs::write(path, self.to_rle_text())?;
+        Ok(())
+    }
+
+    pub fn load_rle<P: AsRef<std::path::Path>>(path:P) -> Result<World, WorldError> {
+        World::from_rle_text(&std::fs::read_to_string(path)?)
+    }
+
+    // e.g. colors from `read_palette`, so that a world can be shown with the exact colors of an earlier export
+    pub fn set_palette(&mut self, colors:Vec<(u8, u8, u8)>) {
+        assert_eq!(colors.len(), self.symbol_count as usize, "The palette must have one color per symbol.");
+        self.symbol_to_color = colors;
+    }
+
+    // shuffles the palette so that symbols which often turn into each other (see `TransitionMatrix::affinity_chain`) get
+    // colors of similar hue, which makes the "flow" between them visible as smooth color shifts. the colors themselves
+    // stay the same. returns, for each symbol, the index of the color it took from the old palette.
+    pub fn harmonize_palette(&mut self, matrix:&TransitionMatrix) -> Vec<usize> {
+        let hue = |color| rgb_to_hsv(color).0;
+        let mut by_hue: Vec<usize> = (0..self.symbol_to_color.len()).collect();
+        by_hue.sort_by(|a, b| hue(self.symbol_to_color[*a]).partial_cmp(&hue(self.symbol_to_color[*b])).unwrap().then(a.cmp(b)));
+        let mut permutation = vec![0; self.symbol_to_color.len()];
+        for (symbol, color_index) in matrix.affinity_chain().into_iter().zip(by_hue) {
+            permutation[symbol as usize] = color_index;
+        }
+        self.symbol_to_color = permutation.iter().map(|i| self.symbol_to_color[*i]).collect();
+        permutation
+    }
+
+    pub fn seed_from_image<P: AsRef<std::path::Path>>(&mut self, path:P) -> Result<(), WorldError> {
+        let img = image::open(path)?.to_rgb();
+        self.seed_from_rgb_image(&img);
+        Ok(())
+    }
+
+    fn seed_from_rgb_image(&mut self, img:&image::RgbImage) {
+        let (width, height) = img.dimensions();
+        // nearest-neighbor resample the image onto the world grid:
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let ix = (x as u64 * width as u64 / self.size as u64) as u32;
+                let iy = (y as u64 * height as u64 / self.size as u64) as u32;
+                let pixel = img.get_pixel(ix, iy);
+                let i = y * self.size + x;
+                self.data[i as usize] = self.closest_symbol((pixel[0], pixel[1], pixel[2]));
+            }
+        }
+        // the whole grid may have changed:
+        self.mark_all_changed();
+    }
+
+    fn closest_symbol(&self, color:(u8, u8, u8)) -> u32 {
+        let (r, g, b) = color;
+        let mut best_symbol = 0;
+        let mut best_distance = u32::MAX;
+        for (symbol, (sr, sg, sb)) in self.symbol_to_color.iter().enumerate() {
+            let dr = i32::from(r) - i32::from(*sr);
+            let dg = i32::from(g) - i32::from(*sg);
+            let db = i32::from(b) - i32::from(*sb);
+            let distance = (dr*dr + dg*dg + db*db) as u32;
+            if distance < best_distance {
+                best_distance = distance;
+                best_symbol = symbol as u32;
+            }
+        }
+        best_symbol
+    }
+
+    pub fn draw_to_buffer(&self, buffer:&mut [u32], buffer_width:usize, buffer_height:usize) {
+        self.draw_cells_to_buffer(buffer, buffer_width, buffer_height, |i| {
+            let v = self.data[i];
+            let (r, g, b) = self.symbol_to_color[v as usize];
+            let mut brightness = self.intensity.as_ref().map_or(1.0, |intensity| f32::from(intensity[i]) / 255.0);
+            if let Some(dim) = self.change_highlight {
+                if !self.cell_changed_flags[i] {
+                    brightness *= dim;
+                }
+            }
+            if brightness == 1.0 {
+                (u32::from(r) << 16) | (u32::from(g) << 8) | u32::from(b)
+            } else {
+                let scale = |c:u8| (f32::from(c) * brightness).round() as u32;
+                (scale(r) << 16) | (scale(g) << 8) | scale(b)
+            }
+        });
+    }
+
+    // like `draw_to_buffer`, but cells fade to black over `fade_frames` frames since they last changed,
+    // so moving things leave trails. needs `track_last_changed` to have been called.
+    pub fn draw_trails_to_buffer(&self, buffer:&mut [u32], buffer_width:usize, buffer_height:usize, fade_frames:u32) {
+        let last_changed = self.last_changed.as_ref().expect("last-changed tracking isn't enabled");
+        self.draw_cells_to_buffer(buffer, buffer_width, buffer_height, |i| {
+            let v = self.data[i];
+            let (r, g, b) = self.symbol_to_color[v as usize];
+            let age = self.frame - last_changed[i];
+            let brightness = 1.0 - age.min(fade_frames) as f32 / fade_frames as f32;
+            let fade = |c:u8| (f32::from(c) * brightness) as u32;
+            (fade(r) << 16) | (fade(g) << 8) | fade(b)
+        });
+    }
+
+    // one 5-6-5 packed pixel per cell, for displays (or transfers) where 0RGB `u32`s are twice as big as they need to be.
+    // unlike `draw_to_buffer`, there's no scaling: `buffer` must be exactly the size of the grid.
+    pub fn draw_to_buffer_rgb565(&self, buffer:&mut [u16]) {
+        assert_eq!(buffer.len(), self.data.len());
+        let palette: Vec<u16> = self.symbol_to_color.iter().map(|(r, g, b)| rgb565(*r, *g, *b)).collect();
+        for (i, pixel) in buffer.iter_mut().enumerate() {
+            *pixel = palette[self.data[self.displayed_cell(i)] as usize];
+        }
+    }
+
+    // the symbols themselves, one byte per cell, to be paired with `symbol_to_color` as the palette.
+    pub fn draw_indexed(&self, buffer:&mut [u8]) {
+        assert_eq!(buffer.len(), self.data.len());
+        assert!(self.symbol_count <= 256, "Indexed output needs at most 256 symbols.");
+        for (i, pixel) in buffer.iter_mut().enumerate() {
+            *pixel = self.data[self.displayed_cell(i)] as u8;
+        }
+    }
+
+    // the index of the cell that's drawn at cell position `i` of the output, taking `view_offset` into account
+    fn displayed_cell(&self, i:usize) -> usize {
+        if self.view_offset == (0, 0) {
+            return i;
+        }
+        let (x, y) = ((i % self.size as usize) as i32, (i / self.size as usize) as i32);
+        (wrap(y - self.view_offset.1, self.size) * self.size + wrap(x - self.view_offset.0, self.size)) as usize
+    }
+
+    // `cell_color` maps a cell index to its 0RGB color
+    fn draw_cells_to_buffer<F: Fn(usize) -> u32>(&self, buffer:&mut [u32], buffer_width:usize, buffer_height:usize, cell_color:F) {
+        let world_size = self.size as usize;
+        let cell_color = |i| cell_color(self.displayed_cell(i));
+        assert_eq!(buffer.len(), buffer_width * buffer_height);
+
+        // the window can be resized to any shape, so use the biggest whole cell size that fits
+        // (anything that doesn't fit at one pixel per cell is averaged down, or cropped without `antialias`):
+        let cell_size = (buffer_width.min(buffer_height) / world_size).max(1);
+        let out_size = buffer_width.min(buffer_height);
+        if self.antialias && out_size < world_size && out_size > 0 {
+            // each pixel covers a (world_size / out_size) square of cells, which usually isn't a whole number of
+            // them, so each cell counts for as much of its area as is under the pixel:
+            let scale = world_size as f64 / out_size as f64;
+            let overlaps = |p:usize| {
+                let (start, end) = (p as f64 * scale, (p + 1) as f64 * scale);
+                (start.floor() as usize..(end.ceil() as usize).min(world_size)).map(move |c| (c, end.min(c as f64 + 1.0) - start.max(c as f64)))
+            };
+            for py in 0..out_size {
+                for px in 0..out_size {
+                    let mut sum = [0.0; 3];
+                    for (y, y_weight) in overlaps(py) {
+                        for (x, x_weight) in overlaps(px) {
+                            let rgb_bits = cell_color(y * world_size + x);
+                            for (channel, s) in sum.iter_mut().enumerate() {
+                                *s += ((rgb_bits >> (16 - 8*channel)) & 0xff) as f64 * x_weight * y_weight;
+                            }
+                        }
+                    }
+                    let average = |channel:usize| (sum[channel] / (scale * scale)).round().min(255.0) as u32;
+                    buffer[py * buffer_width + px] = (average(0) << 16) | (average(1) << 8) | average(2);
+                }
+            }
+        } else if cell_size == 1 && buffer_width == world_size && buffer_height == world_size {
+            for (i, pixel) in buffer.iter_mut().enumerate() {
+                *pixel = cell_color(i);
+            }
+        } else {
+            fill_cell_blocks(buffer, buffer_width, buffer_height, world_size, cell_size, cell_color);
+        }
+
+    }
+
+    // number of cells holding each symbol:
+    pub fn histogram(&self) -> Vec<u32> {
+        let mut histogram = vec![0; self.symbol_count as usize];
+        for v in self.data.iter() {
+            histogram[*v as usize] += 1;
+        }
+        histogram
+    }
+
+    // the share of the grid held by the most common symbol (1.0 means it has taken over completely)
+    pub fn dominant_fraction(&self) -> f32 {
+        *self.histogram().iter().max().unwrap() as f32 / self.data.len() as f32
+    }
+
+    pub fn count_symbol(&self, symbol:u32) -> u32 {
+        self.data.iter().filter(|v| **v == symbol).count() as u32
+    }
+
+    // sorted distinct symbols currently in the grid
+    pub fn present_symbols(&self) -> Vec<u32> {
+        self.histogram().iter().enumerate().filter(|(_, count)| **count > 0).map(|(symbol, _)| symbol as u32).collect()
+    }
+
+    // steps the world on each `next()` and yields a summary of the resulting frame
+    pub fn iter_frames(self) -> impl Iterator<Item = FrameView> {
+        FrameIter { world: self, index: 0 }
+    }
+
+    // number of (toroidally) adjacent cell pairs holding different symbols. high means lots of interfaces/texture,
+    // zero means one uniform region. each pair is counted once by only looking right and down.
+    pub fn boundary_energy(&self) -> u64 {
+        let world_size = self.size;
+        let mut energy = 0;
+        for y in 0..world_size {
+            for x in 0..world_size {
+                let v = self.data[(y*world_size + x) as usize];
+                let right = self.data[(y*world_size + wrap(x as i32 + 1, world_size)) as usize];
+                let down = self.data[(wrap(y as i32 + 1, world_size)*world_size + x) as usize];
+                if v != right { energy += 1; }
+                if v != down { energy += 1; }
+            }
+        }
+        energy
+    }
+
+    // shannon entropy (in bits) of the symbol distribution: 0 for a uniform grid, up to log2(symbol_count) for an even mix
+    pub fn symbol_entropy(&self) -> f64 {
+        sorted_counts_entropy(&self.sorted_symbol_counts(), self.data.len())
+    }
+
+    // how alike the grid is to itself shifted by 0..=`max_lag` cells: for each lag, the fraction of cells holding the same
+    // symbol as the cell that far to their right or below (wrapping around), averaged over the two directions. fine noise
+    // drops to the chance level within a lag or two, while big coherent regions stay high for about as many cells as
+    // they're across, and periodic patterns come back up at multiples of their period.
+    pub fn autocorrelation(&self, max_lag:u32) -> Vec<f32> {
+        let n = self.size;
+        (0..=max_lag).map(|lag| {
+            let mut matches = 0u64;
+            for y in 0..n {
+                for x in 0..n {
+                    let v = self.data[(y*n + x) as usize];
+                    if v == self.data[(y*n + wrap((x + lag) as i32, n)) as usize] { matches += 1; }
+                    if v == self.data[(wrap((y + lag) as i32, n)*n + x) as usize] { matches += 1; }
+                }
+            }
+            matches as f32 / (2 * self.data.len()) as f32
+        }).collect()
+    }
+
+    // the entropy of each cell's neighborhood, scaled to 0..1 (1 being as varied as a neighborhood can be with this many
+    // symbols). uniform regions are 0 and the "active frontiers" between them light up, which shows where the action is
+    // without needing a history of changes.
+    pub fn local_variety_map(&self) -> Vec<f32> {
+        let neighborhood_cell_count = (2*self.neighborhood_radius + 1).pow(2);
+        let max_entropy = (self.symbol_count.min(neighborhood_cell_count) as f64).log2();
+        (0..self.data.len() as u32).map(|i| {
+            if max_entropy == 0.0 {
+                return 0.0;
+            }
+            let mut counts: Vec<u32> = neighborhood_counts(&self.data, self.size, self.neighborhood_radius, (i % self.size, i / self.size)).into_iter().map(|(_, count)| count).collect();
+            counts.sort_unstable_by(|a, b| b.cmp(a));
+            (sorted_counts_entropy(&counts, neighborhood_cell_count as usize) / max_entropy) as f32
+        }).collect()
+    }
+
+    // the world as drawn by `draw_to_buffer`, at `upscale` pixels per cell. `buffer` is just scratch space, so that it
+    // can be reused between frames.
+    pub fn to_rgb_image(&self, buffer:&mut Vec<u32>, upscale:u32) -> image::RgbImage {
+        let image_size = self.size * upscale;
+        buffer.resize((image_size * image_size) as usize, 0);
+        self.draw_to_buffer(buffer, image_size as usize, image_size as usize);
+        image::RgbImage::from_fn(image_size, image_size, |x, y| {
+            let rgb_bits = buffer[(y * image_size + x) as usize];
+            image::Rgb([(rgb_bits >> 16) as u8, (rgb_bits >> 8) as u8, rgb_bits as u8])
+        })
+    }
+
+    // `local_variety_map` as a grayscale image (one pixel per cell, white being the most varied)
+    pub fn save_local_variety_map<P: AsRef<std::path::Path>>(&self, path:P) -> Result<(), WorldError> {
+        let variety = self.local_variety_map();
+        let img = image::GrayImage::from_fn(self.size, self.size, |x, y| {
+            image::Luma([(variety[(y*self.size + x) as usize] * 255.0).round() as u8])
+        });
+        img.save(path)?;
+        Ok(())
+    }
+
+    // deflate-compressed size of the run-length encoded grid, relative to the raw grid size. pure noise barely compresses,
+    // uniform grids compress to almost nothing, and interesting structure sits somewhere in between.
+    pub fn complexity_score(&self) -> f64 {
+        use flate2::{Compression, write::DeflateEncoder};
+        use std::io::Write;
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        for (symbol, run_length) in self.to_rle() {
+            encoder.write_all(&symbol.to_le_bytes()).unwrap();
+            encoder.write_all(&run_length.to_le_bytes()).unwrap();
+        }
+        let compressed_len = encoder.finish().unwrap().len();
+        let raw_len = self.data.len() * mem::size_of::<u32>();
+        compressed_len as f64 / raw_len as f64
+    }
+
+    // a hash of how a copy of this world behaves over the next `frames` steps, for spotting the same dynamics turning up
+    // under different seeds or parameters. it only looks at things that don't depend on which symbol is which: the
+    // symbol entropy over time (downsampled to 8 points) and the end histogram (sorted, as fractions of the grid), both
+    // coarsely quantized so that nearly identical runs still collide.
+    pub fn behavior_fingerprint(&self, frames:usize) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut world = self.clone();
+        let mut entropy_curve = Vec::with_capacity(frames);
+        for _ in 0..frames {
+            world.step();
+            entropy_curve.push(sorted_counts_entropy(&world.sorted_symbol_counts(), world.data.len()));
+        }
+        let sample_count = 8.min(entropy_curve.len());
+        let entropy_samples: Vec<u32> = (0..sample_count).map(|i| {
+            let entropy = entropy_curve[i * (entropy_curve.len() - 1) / (sample_count - 1).max(1)];
+            (entropy * 4.0).round() as u32 // quarter-bit steps
+        }).collect();
+        let end_histogram: Vec<u32> = world.sorted_symbol_counts().iter()
+            .map(|count| (*count as f64 / world.data.len() as f64 * 32.0).round() as u32)
+            .filter(|v| *v > 0)
+            .collect();
+
+        let mut s = DefaultHasher::new();
+        entropy_samples.hash(&mut s);
+        end_histogram.hash(&mut s);
+        s.finish()
+    }
+
+    // the histogram with the symbol identities thrown away (most common first, absent symbols dropped)
+    fn sorted_symbol_counts(&self) -> Vec<u32> {
+        let mut counts: Vec<u32> = self.histogram().into_iter().filter(|count| *count > 0).collect();
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        counts
+    }
+
+    // fraction of cells that match their mirror image (left-right and top-bottom) or their 180 degree rotation. on a
+    // torus a mirror axis can be anywhere, so the best of every axis position is used. the rotation is checked about
+    // the point where the best two mirror axes cross.
+    pub fn symmetry_score(&self) -> SymmetryScore {
+        let n = self.size;
+        let cell_count = self.data.len() as f32;
+        let at = |x:u32, y:u32| self.data[(y*n + x) as usize];
+        // cells x and x' are mirrored about "axis" c when x + x' == c (mod n), which covers axes both through and between cells:
+        let (mut best_horizontal, mut best_horizontal_axis) = (0, 0);
+        let (mut best_vertical, mut best_vertical_axis) = (0, 0);
+        for c in 0..n {
+            let mut horizontal_matches = 0;
+            let mut vertical_matches = 0;
+            for y in 0..n {
+                for x in 0..n {
+                    if at(x, y) == at((c + n - x) % n, y) { horizontal_matches += 1; }
+                    if at(x, y) == at(x, (c + n - y) % n) { vertical_matches += 1; }
+                }
+            }
+            if horizontal_matches > best_horizontal {
+                best_horizontal = horizontal_matches;
+                best_horizontal_axis = c;
+            }
+            if vertical_matches > best_vertical {
+                best_vertical = vertical_matches;
+                best_vertical_axis = c;
+            }
+        }
+        let mut rotational_matches = 0;
+        for y in 0..n {
+            for x in 0..n {
+                if at(x, y) == at((best_horizontal_axis + n - x) % n, (best_vertical_axis + n - y) % n) { rotational_matches += 1; }
+            }
+        }
+        SymmetryScore {
+            horizontal: best_horizontal as f32 / cell_count,
+            vertical: best_vertical as f32 / cell_count,
+            rotational: rotational_matches as f32 / cell_count,
+        }
+    }
+
+    // (symbol, run_length) pairs over `data` in row-major order:
+    pub fn to_rle(&self) -> Vec<(u32, u32)> {
+        rle_encode(&self.data)
+    }
+
+    /// the textual counterpart to `draw_to_buffer`, for readable assertions on small worlds: one character per cell
+    /// (`0`-`9`, then `a`-`z`, then `A`-`Z`) and a newline after each row. symbols past the 62nd wrap back around to
+    /// `0`, so worlds with more symbols than that can't be told apart reliably.
+    ///
+    /// ```
+    /// use color_rules_cellular_automata::World;
+    /// let mut world = World::new(2, 12, 1.0, 1);
+    /// world.data = vec![0, 1, 10, 11];
+    /// assert_eq!(world.to_ascii(), "01\nab\n");
+    /// ```
+    pub fn to_ascii(&self) -> String {
+        let characters: Vec<char> = ('0'..='9').chain('a'..='z').chain('A'..='Z').collect();
+        let mut ascii = String::with_capacity(((self.size + 1) * self.size) as usize);
+        for row in self.data.chunks(self.size as usize) {
+            ascii.extend(row.iter().map(|v| characters[*v as usize % characters.len()]));
+            ascii.push('\n');
+        }
+        ascii
+    }
+
+    pub fn _draw_to_console(&self) {
+        use ansi_term::Colour::RGB;
+        use ansi_term::ANSIStrings;
+
+        let mut ansi_characters = Vec::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let i = y*self.size + x;
+                let v = self.data[i as usize];
+                let (r, g, b) = self.symbol_to_color[v as usize];
+                let c = RGB(r, g, b).paint("▓▓");
+                ansi_characters.push(c);
+            }
+            ansi_characters.push(RGB(0, 0, 0).paint("\n"));
+        }
+        println!("{}", ANSIStrings(&ansi_characters));
+    }
+}
+
+// the mean of coordinates on an axis that wraps around after `size`, found by treating them as angles on a circle (so
+// e.g. the mean of 1 and size-1 is 0, not size/2). None if there's no meaningful mean, i.e. they're spread evenly.
+fn circular_mean<I: Iterator<Item=u32>>(coordinates:I, size:u32) -> Option<f64> {
+    let (mut cos, mut sin, mut count) = (0.0, 0.0, 0);
+    for c in coordinates {
+        let angle = c as f64 / size as f64 * std::f64::consts::PI * 2.0;
+        cos += angle.cos();
+        sin += angle.sin();
+        count += 1;
+    }
+    if count == 0 || cos.hypot(sin) < 1e-9 * count as f64 {
+        return None;
+    }
+    Some(sin.atan2(cos).rem_euclid(std::f64::consts::PI * 2.0) / (std::f64::consts::PI * 2.0) * size as f64)
+}
+
+// shannon entropy (in bits) of the symbol distribution. the counts are taken sorted so that the result is exactly the
+// same for any permutation of the symbols (float addition isn't associative).
+fn sorted_counts_entropy(sorted_counts:&[u32], total:usize) -> f64 {
+    sorted_counts.iter().map(|count| {
+        let p = *count as f64 / total as f64;
+        -p * p.log2()
+    }).sum()
+}
+
+// now we (in effect) run a "erosion" over the `cell_changed_flag` grid to produce the `neighborhood_changed_flag` grid.
+// more concretely: if a cell and all its neighbors (within `radius`) did not change, then we set the neighborhood_changed
+// flag at that postition to false.
+fn erode_change_flags(cell_changed_flags: &[bool], neighborhood_changed_flags: &mut [bool], world_size:u32, radius:u32) {
+    let r = radius as i32;
+    let erode = |(i, neighborhood_changed_flag): (usize, &mut bool)| {
+        let xc = i as u32 % world_size;
+        let yc = (i as u32 - xc) / world_size;
+        for y in (yc as i32 - r)..(yc as i32 + r + 1) {
+            for x in (xc as i32 - r)..(xc as i32 + r + 1) {
+                let ii = wrap(y, world_size)*world_size + wrap(x, world_size);
+                let changed = cell_changed_flags[ii as usize];
+                if changed {
+                    *neighborhood_changed_flag = true;
+                    return;
+                }
+            }
+        }
+        *neighborhood_changed_flag = false;
+    };
+    #[cfg(feature="parallel")]
+    neighborhood_changed_flags.par_iter_mut().enumerate().for_each(erode);
+    #[cfg(not(feature="parallel"))]
+    neighborhood_changed_flags.iter_mut().enumerate().for_each(erode);
+}
+
+// how many rules `World::new` generates (see the derivation in `generate`): it keeps doubling until a neighborhood is
+// almost certain to match at least one of them. with many symbols and long rules the odds of a match can be so small
+// that this would never finish, so it gives up (returning `None`) past a million rules.
+fn generated_rule_count(symbol_count:u32, avg_symbols_per_rule:f32, neighborhood_cell_count:u32) -> Option<u32> {
+    let mut rule_count = 1;
+    let mut prob_match = 0.0;
+    while prob_match < 0.999 {
+      rule_count *= 2;
+      if rule_count > 1 << 20 {
+          return None;
+      }
+      let a = (1.0 - (1.0 / symbol_count as f32)).powf(neighborhood_cell_count as f32);
+      let b = 1.0 - (1.0 - a).powf(avg_symbols_per_rule);
+      prob_match = 1.0 - b.powf(rule_count as f32);
+    }
+    Some(rule_count)
+}
+
+// why `World::new` would reject these generation parameters, if it would (e.g. for skipping them in a sweep)
+pub fn check_generation_params(symbol_count:u32, avg_symbols_per_rule:f32) -> Result<(), String> {
+    if symbol_count < 2 {
+        return Err(format!("{} symbols is too few (there have to be at least 2)", symbol_count));
+    }
+    if avg_symbols_per_rule <= 0.0 || avg_symbols_per_rule >= symbol_count as f32 {
+        return Err(format!("the average symbols per rule has to be positive and less than the symbol count ({})", symbol_count));
+    }
+    generated_rule_count(symbol_count, avg_symbols_per_rule, 9).map(|_| ()).ok_or_else(|| "too many rules would be needed".to_string())
+}
+
+// weights for `World::randomize_weighted` that leave symbol 0 as the background and share `density` evenly between the
+// rest of the symbols
+pub fn spark_weights(symbol_count:u32, density:f32) -> Vec<f32> {
+    assert!((0.0..=1.0).contains(&density), "Spark density must be between 0 and 1.");
+    assert!(symbol_count >= 2, "A spark needs at least 2 symbols.");
+    std::iter::once(1.0 - density).chain((1..symbol_count).map(|_| density / (symbol_count - 1) as f32)).collect()
+}
+
+// a copy of a grid with every cell moved `offset` cells over (wrapping around the edges), like `World::view_offset` does
+// when drawing. for applying the same roll to exported frames.
+pub fn roll_grid(data:&[u32], world_size:u32, offset:(i32, i32)) -> Vec<u32> {
+    assert_eq!(data.len(), (world_size * world_size) as usize);
+    let mut rolled = vec![0; data.len()];
+    for y in 0..world_size as i32 {
+        for x in 0..world_size as i32 {
+            let to = wrap(y + offset.1, world_size) * world_size + wrap(x + offset.0, world_size);
+            rolled[to as usize] = data[(y * world_size as i32 + x) as usize];
+        }
+    }
+    rolled
+}
+
+// draws each cell of a world_size x world_size grid as a cell_size x cell_size block of `cell_value(i)` (cropping
+// whatever doesn't fit in the buffer). shared by the window (0RGB pixels) and the upscaled gif exports (palette indices).
+fn fill_cell_blocks<T: Copy, F: Fn(usize) -> T>(buffer:&mut [T], buffer_width:usize, buffer_height:usize, world_size:usize, cell_size:usize, cell_value:F) {
+    // loop over the "cells":
+    for y in 0..world_size {
+        for x in 0..world_size {
+            let value = cell_value(y * world_size + x);
+            // fill in this cell:
+            for wy in (y*cell_size)..((y+1)*cell_size).min(buffer_height) {
+                for wx in (x*cell_size)..((x+1)*cell_size).min(buffer_width) {
+                    buffer[wy * buffer_width + wx] = value;
+                }
+            }
+        }
+    }
+}
+
+// toroidal wrap of a (possibly out-of-bounds) coordinate:
+fn wrap(v:i32, world_size:u32) -> u32 {
+    v.rem_euclid(world_size as i32) as u32
+}
+
+// what `step_within` zips together for each cell: its index, then its new value, changed flag, neighborhood changed
+// flags (this step's and last step's) and frozen flag
+type CellUpdate<'a> = (usize, ((((&'a mut u32, &'a mut bool), &'a bool), &'a bool), &'a mut bool));
+
+// `motion` is whether anything in the neighborhood changed last step.
+// everything about a world (other than its grid) that a transition depends on
+struct TransitionParams<'a> {
+    world_size: u32,
+    radius: u32,
+    rule_phases: &'a [Vec<WorldRule>],
+    phase: usize, // which of `rule_phases` applies to this step
+    rule_mask: Option<&'a [bool]>,
+    locked_symbols: &'a std::collections::HashSet<u32>,
+    mode: TransitionMode,
+    custom_transition: Option<&'a dyn TransitionFn>,
+}
+
+// returns the cell's next value, and the index of the rule that produced it (None if no rule did)
+fn compute_transition(prev_data: &[u32], params: &TransitionParams, pos:(u32, u32), motion:bool) -> (u32, Option<usize>) {
+    use std::collections::HashSet;
+
+    let TransitionParams { world_size, radius, rule_phases, phase, rule_mask, locked_symbols, mode, custom_transition } = *params;
+    let rules = &rule_phases[phase];
+    let (xc, yc) = pos;
+    let current_value = prev_data[(yc*world_size + xc) as usize];
+    if locked_symbols.contains(&current_value) {
+        return (current_value, None);
+    }
+
+    if let Some(transition) = custom_transition {
+        let mut neighborhood = [0; 9];
+        for (i, (dx, dy)) in (-1..2).flat_map(|dy| (-1..2).map(move |dx| (dx, dy))).enumerate() {
+            neighborhood[i] = prev_data[(wrap(yc as i32 + dy, world_size)*world_size + wrap(xc as i32 + dx, world_size)) as usize];
+        }
+        return (transition.transition(&neighborhood, current_value), None);
+    }
+
+    if mode == TransitionMode::Majority {
+        return (majority_symbol(prev_data, world_size, radius, pos), None);
+    }
+
+    // count symbols in neighborhood:
+    let r = radius as i32;
+    let mut symbol_counts_set = HashSet::<u32>::with_capacity(((2*r + 1)*(2*r + 1)) as usize);
+    for y in (yc as i32 - r)..(yc as i32 + r + 1) {
+        for x in (xc as i32 - r)..(xc as i32 + r + 1) {
+            let i = wrap(y, world_size)*world_size + wrap(x, world_size);
+            let v = prev_data[i as usize];
+            symbol_counts_set.insert(v);
+        }
+    }
+
+    // find first rule that matches:
+    for (i, rule) in rules.iter().enumerate() {
+        if rule.requires_motion && !motion {
+            continue;
+        }
+        if let Some(mask) = rule_mask {
+            if !mask[i] {
+                continue;
+            }
+        }
+        let mut found_non_match = false;
+        for symbol in rule.symbols_needed.iter() {
+            if !symbol_counts_set.contains(symbol) {
+                found_non_match = true;
+                break;
+            }
+        }
+        if !found_non_match && rule.min_count > 1 {
+            found_non_match = rule.symbols_needed.iter().any(|symbol| neighborhood_symbol_count(prev_data, world_size, radius, pos, *symbol) < rule.min_count);
+        }
+        if !found_non_match {
+            return (rule.output_symbol, Some(i));
+        }
+    }
+
+    if mode == TransitionMode::RulesThenMajority {
+        return (majority_symbol(prev_data, world_size, radius, pos), None);
+    }
+
+    // by default keep the same value:
+    (current_value, None)
+}
+
+// whether a neighborhood made up entirely of `symbol` would stay that way (in either motion state). `radius` is at least
+// 1 here because custom transitions always look at a 3x3 neighborhood.
+fn quiescent_is_stable(symbol:u32, params:&TransitionParams) -> bool {
+    let size = 2*params.radius.max(1) + 1;
+    let grid = vec![symbol; (size*size) as usize];
+    let params = TransitionParams { world_size: size, ..*params };
+    [false, true].iter().all(|motion| compute_transition(&grid, &params, (size/2, size/2), *motion).0 == symbol)
+}
+
+// stops at the first cell that isn't `symbol`, so it's cheap for the (common) non-uniform case too
+fn neighborhood_is_uniform(data: &[u32], world_size:u32, radius:u32, pos:(u32, u32), symbol:u32) -> bool {
+    let (xc, yc) = pos;
+    let r = radius as i32;
+    ((yc as i32 - r)..(yc as i32 + r + 1)).all(|y| {
+        ((xc as i32 - r)..(xc as i32 + r + 1)).all(|x| data[(wrap(y, world_size)*world_size + wrap(x, world_size)) as usize] == symbol)
+    })
+}
+
+fn neighborhood_symbol_count(prev_data: &[u32], world_size:u32, radius:u32, pos:(u32, u32), symbol:u32) -> u32 {
+    let (xc, yc) = pos;
+    let r = radius as i32;
+    let mut count = 0;
+    for y in (yc as i32 - r)..(yc as i32 + r + 1) {
+        for x in (xc as i32 - r)..(xc as i32 + r + 1) {
+            if prev_data[(wrap(y, world_size)*world_size + wrap(x, world_size)) as usize] == symbol {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+// (symbol, count) for each symbol in the neighborhood, in the order they're first seen
+fn neighborhood_counts(data: &[u32], world_size:u32, radius:u32, pos:(u32, u32)) -> Vec<(u32, u32)> {
+    let (xc, yc) = pos;
+    let r = radius as i32;
+    let mut counts: Vec<(u32, u32)> = Vec::with_capacity(((2*r + 1)*(2*r + 1)) as usize);
+    for y in (yc as i32 - r)..(yc as i32 + r + 1) {
+        for x in (xc as i32 - r)..(xc as i32 + r + 1) {
+            let v = data[(wrap(y, world_size)*world_size + wrap(x, world_size)) as usize];
+            match counts.iter_mut().find(|(symbol, _)| *symbol == v) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((v, 1)),
+            }
+        }
+    }
+    counts
+}
+
+// the most common symbol in the neighborhood (ties go to the current value if it's one of the most common, and
+// otherwise to the lowest symbol, so that the result doesn't depend on iteration order)
+fn majority_symbol(prev_data: &[u32], world_size:u32, radius:u32, pos:(u32, u32)) -> u32 {
+    let (xc, yc) = pos;
+    let current_value = prev_data[(yc*world_size + xc) as usize];
+    let counts = neighborhood_counts(prev_data, world_size, radius, pos);
+    let max_count = counts.iter().map(|(_, count)| *count).max().unwrap();
+    if counts.contains(&(current_value, max_count)) {
+        return current_value;
+    }
+    counts.iter().filter(|(_, count)| *count == max_count).map(|(symbol, _)| *symbol).min().unwrap()
+}
+
+// tuning for the heuristics that decide whether a seed is interesting
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature="config-file", derive(Serialize, Deserialize), serde(default))]
+pub struct NoveltySearchParams {
+    pub sample_frame_count: usize, // metrics are taken over this many frames (after the burn-in)
+    pub min_end_cell_diff: u32,
+    // the false-positive guard compares which cells changed over two windows of this many frames at the end of the
+    // sample. oscillators with a period longer than the window can slip past it, so raise it to catch slower ones.
+    pub change_window: usize,
+    pub change_window_gap: usize, // frames skipped between the two windows
+    // frames to let the random initial soup settle before sampling starts, so it doesn't inflate the unique frame count
+    pub burn_in_frames: usize,
+}
+
+// what the main program starts with
+impl Default for NoveltySearchParams {
+    fn default() -> NoveltySearchParams {
+        NoveltySearchParams { sample_frame_count: 400, min_end_cell_diff: 25, change_window: 5, change_window_gap: 0, burn_in_frames: 0 }
+    }
+}
+
+impl NoveltySearchParams {
+    // the number of frames a run needs for its metrics to be complete
+    pub fn sample_end_frame(&self) -> usize {
+        self.burn_in_frames + self.sample_frame_count
+    }
+}
+
+// accumulates the novelty metrics of a run, one frame at a time
+pub struct NoveltyTracker {
+    burn_in_frames: usize,
+    sample_frame_count: usize,
+    change_window: usize,
+    change_window_gap: usize,
+    unique_frame_hashes: std::collections::HashSet<u64>,
+    // we compare these at the end and if they're the same then that likely means
+    // that the changes are just lots of little repeating oscillations that,
+    // when "muliplied" together cause the frames to be unique (thus giving us a false positive).
+    last_frames_cell_changes_anded_1: Vec<bool>, // second last batch of `change_window` frames, ANDed together
+    last_frames_cell_changes_anded_2: Vec<bool>, // last batch of `change_window` frames, ANDed together
+    world_size: u32,
+    // (frame, mean x, mean y) of the cells that changed in each sampled frame, for `wavefront`
+    change_centers: Vec<(usize, Option<f64>, Option<f64>)>,
+}
+
+impl NoveltyTracker {
+    pub fn new(params:&NoveltySearchParams, cell_count:usize) -> NoveltyTracker {
+        assert!(params.change_window > 0, "change_window must be positive.");
+        assert!(2*params.change_window + params.change_window_gap <= params.sample_frame_count, "Both change windows (and the gap between them) must fit within sample_frame_count.");
+        NoveltyTracker {
+            burn_in_frames: params.burn_in_frames,
+            sample_frame_count: params.sample_frame_count,
+            change_window: params.change_window,
+            change_window_gap: params.change_window_gap,
+            unique_frame_hashes: std::collections::HashSet::new(),
+            last_frames_cell_changes_anded_1: vec![false; cell_count],
+            last_frames_cell_changes_anded_2: vec![false; cell_count],
+            world_size: (cell_count as f64).sqrt() as u32,
+            change_centers: vec![],
+        }
+    }
+
+    // `count` is the index of the frame that `world` has just stepped to
+    pub fn record_frame(&mut self, count:usize, world:&World) {
+        if count < self.burn_in_frames {
+            return;
+        }
+        let count = count - self.burn_in_frames;
+        let sample_frame_count = self.sample_frame_count;
+        if count <= sample_frame_count {
+            self.unique_frame_hashes.insert( calculate_vec_hash(&world.data) );
+        }
+        if count < sample_frame_count && !world.is_static() {
+            let size = self.world_size;
+            let changed = || world.cell_changed_flags.iter().enumerate().filter(|(_, v)| **v).map(|(i, _)| i as u32);
+            self.change_centers.push((count, circular_mean(changed().map(|i| i % size), size), circular_mean(changed().map(|i| i / size), size)));
+        }
+        let window_2_start = sample_frame_count - self.change_window;
+        let window_1_end = window_2_start - self.change_window_gap;
+        let window_1_start = window_1_end - self.change_window;
+        if count > window_1_start && count <= window_1_end {
+            let anded = &mut self.last_frames_cell_changes_anded_1;
+            world.cell_changed_flags.iter().enumerate().for_each(|(i, v)| {
+                if *v {
+                    anded[i] = true;
+                }
+            });
+        }
+        if count > window_2_start && count <= sample_frame_count {
+            let anded = &mut self.last_frames_cell_changes_anded_2;
+            world.cell_changed_flags.iter().enumerate().for_each(|(i, v)| {
+                if *v {
+                    anded[i] = true;
+                }
+            });
+        }
+    }
+
+    pub fn unique_frame_count(&self) -> usize {
+        self.unique_frame_hashes.len()
+    }
+
+    pub fn cell_change_diff_count(&self) -> u32 {
+        bool_vec_diff_count(&self.last_frames_cell_changes_anded_1, &self.last_frames_cell_changes_anded_2)
+    }
+
+    // whether the changes so far have been moving steadily in one direction, like a travelling front or wave. the center
+    // of the changed cells is followed from frame to frame (taking the shortest way around the edges), and a straight
+    // line is fit through its path on each axis. an axis along which the changes are spread evenly (e.g. along a front
+    // that spans the whole world) has no center, and so doesn't move.
+    pub fn wavefront(&self) -> Option<Wavefront> {
+        let size = self.world_size as f64;
+        // returns (slope, residual sum of squares, total sum of squares)
+        let fit_axis = |axis:usize| -> Option<(f64, f64, f64)> {
+            let mut path: Vec<(f64, f64)> = vec![];
+            for (frame, x, y) in self.change_centers.iter() {
+                let center = match if axis == 0 { x } else { y } { Some(c) => *c, None => continue };
+                let position = match path.last() {
+                    Some((_, last)) => {
+                        let delta = center - last.rem_euclid(size);
+                        last + delta - size * (delta / size).round()
+                    }
+                    None => center,
+                };
+                path.push((*frame as f64, position));
+            }
+            if path.len() < 3 {
+                return None;
+            }
+            let n = path.len() as f64;
+            let mean_t = path.iter().map(|(t, _)| t).sum::<f64>() / n;
+            let mean_p = path.iter().map(|(_, p)| p).sum::<f64>() / n;
+            let slope = path.iter().map(|(t, p)| (t - mean_t) * (p - mean_p)).sum::<f64>() / path.iter().map(|(t, _)| (t - mean_t).powi(2)).sum::<f64>();
+            let residual = path.iter().map(|(t, p)| (p - mean_p - slope * (t - mean_t)).powi(2)).sum();
+            let total = path.iter().map(|(_, p)| (p - mean_p).powi(2)).sum();
+            Some((slope, residual, total))
+        };
+        let (x, y) = (fit_axis(0), fit_axis(1));
+        let (residual, total) = [x, y].iter().flatten().fold((0.0, 0.0), |(r, t), (_, residual, total)| (r + residual, t + total));
+        if total < 1e-9 {
+            return None;
+        }
+        let velocity = (x.map_or(0.0, |(slope, _, _)| slope), y.map_or(0.0, |(slope, _, _)| slope));
+        let wavefront = Wavefront { velocity, speed: velocity.0.hypot(velocity.1), coherence: 1.0 - residual / total };
+        // most of the movement has to be explained by the straight line, and it has to actually get somewhere:
+        if wavefront.coherence >= 0.9 && wavefront.speed >= 0.1 { Some(wavefront) } else { None }
+    }
+}
+
+// the steady movement found by `NoveltyTracker::wavefront`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wavefront {
+    pub velocity: (f64, f64), // cells per frame along x and y
+    pub speed: f64,
+    pub coherence: f64, // the fraction of the center's movement explained by the fit (1 is a perfectly straight, steady path)
+}
+
+// lets a search give up on a seed before its sample is complete, once it can tell the seed won't be accepted anyway:
+// - as soon as a sampled frame repeats, since `NoveltyFitness` needs every sampled frame to be unique (this alone can
+//   never reject a seed that the full run would have accepted, and catches the many worlds that settle into a cycle)
+// - at each checkpoint (a fraction of the sample, e.g. 0.25), if fewer than `min_changed_fraction` of the cells
+//   changed in the last step, i.e. the activity is dying out. this one is a heuristic, so it's off when set to 0.
+#[derive(Clone, Debug)]
+pub struct EarlyDecisionPolicy {
+    pub checkpoints: Vec<f32>,
+    pub min_changed_fraction: f32,
+}
+
+impl Default for EarlyDecisionPolicy {
+    fn default() -> EarlyDecisionPolicy {
+        EarlyDecisionPolicy { checkpoints: vec![0.25, 0.5], min_changed_fraction: 0.0 }
+    }
+}
+
+impl EarlyDecisionPolicy {
+    // `count` is the number of frames stepped so far, and `novelty` must have recorded all of them
+    pub fn should_abort(&self, count:usize, params:&NoveltySearchParams, novelty:&NoveltyTracker, world:&World) -> bool {
+        if count <= params.burn_in_frames || count >= params.sample_end_frame() {
+            return false;
+        }
+        let sampled = count - params.burn_in_frames;
+        if novelty.unique_frame_count() < sampled {
+            return true;
+        }
+        let at_checkpoint = self.checkpoints.iter().any(|c| (c * params.sample_frame_count as f32).round() as usize == sampled);
+        let changed_count = world.cell_changed_flags.iter().filter(|v| **v).count();
+        at_checkpoint && (changed_count as f32) < self.min_changed_fraction * world.data.len() as f32
+    }
+}
+
+// counts how often each symbol turned into each other symbol over a run (record it after every step), which shows the
+// "flow" between symbols, e.g. that 3 almost always becomes 7
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransitionMatrix {
+    symbol_count: u32,
+    counts: Vec<u64>, // row-major, indexed by [old symbol][new symbol]
+}
+
+impl TransitionMatrix {
+    pub fn new(symbol_count:u32) -> TransitionMatrix {
+        TransitionMatrix { symbol_count, counts: vec![0; (symbol_count * symbol_count) as usize] }
+    }
+
+    // adds the changes made by `world`'s last step
+    pub fn record(&mut self, world:&World) {
+        assert_eq!(world.symbol_count, self.symbol_count, "The matrix must have the same symbol count as the world.");
+        for (i, _) in world.cell_changed_flags.iter().enumerate().filter(|(_, changed)| **changed) {
+            self.counts[(world.prev_data[i] * self.symbol_count + world.data[i]) as usize] += 1;
+        }
+    }
+
+    pub fn count(&self, old_symbol:u32, new_symbol:u32) -> u64 {
+        self.counts[(old_symbol * self.symbol_count + new_symbol) as usize]
+    }
+
+    // the symbols in an order where ones that often turn into each other (in either direction) are next to each other:
+    // starting from the symbol with the most transitions, it keeps adding whichever remaining symbol interconverts most
+    // with the last one (ties go to the lowest symbol, so the order is deterministic)
+    pub fn affinity_chain(&self) -> Vec<u32> {
+        let n = self.symbol_count;
+        let affinity = |a:u32, b:u32| self.count(a, b) + self.count(b, a);
+        let total = |a:u32| (0..n).filter(|b| *b != a).map(|b| affinity(a, b)).sum::<u64>();
+        let mut remaining: Vec<u32> = (0..n).collect();
+        let mut chain = vec![];
+        let mut current = *remaining.iter().max_by_key(|s| (total(**s), std::cmp::Reverse(**s))).unwrap();
+        loop {
+            remaining.retain(|s| *s != current);
+            chain.push(current);
+            match remaining.iter().max_by_key(|s| (affinity(current, **s), std::cmp::Reverse(**s))) {
+                Some(next) => current = *next,
+                None => return chain,
+            }
+        }
+    }
+
+    // each row scaled to sum to 1 (i.e. the probability of what a symbol turns into when it changes), so that runs of
+    // different lengths can be compared. rows for symbols that never changed are all zero.
+    pub fn probabilities(&self) -> Vec<Vec<f64>> {
+        self.counts.chunks(self.symbol_count as usize).map(|row| {
+            let total: u64 = row.iter().sum();
+            row.iter().map(|count| if total == 0 { 0.0 } else { *count as f64 / total as f64 }).collect()
+        }).collect()
+    }
+}
+
+// counts how often each set of symbols (a `World::neighborhood_mask`) shows up as a neighborhood over a run (record it
+// after every step). the common ones are where the rules matter most when designing or pruning them, and the rare ones
+// can mostly be ignored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NeighborhoodHistogram {
+    counts: std::collections::HashMap<u64, u64>,
+    total: u64, // cells recorded, over all frames
+}
+
+impl NeighborhoodHistogram {
+    pub fn new() -> NeighborhoodHistogram {
+        NeighborhoodHistogram::default()
+    }
+
+    pub fn record(&mut self, world:&World) {
+        for (mask, count) in world.neighborhood_histogram() {
+            *self.counts.entry(mask).or_insert(0) += count;
+            self.total += count;
+        }
+    }
+
+    pub fn count(&self, mask:u64) -> u64 {
+        self.counts.get(&mask).copied().unwrap_or(0)
+    }
+
+    // number of distinct neighborhoods seen
+    pub fn distinct_count(&self) -> usize {
+        self.counts.len()
+    }
+
+    // the `n` most common neighborhoods, each with the fraction of all recorded cells that had it (ties go to the
+    // lower mask, so the order is deterministic)
+    pub fn top(&self, n:usize) -> Vec<(u64, f64)> {
+        let mut counts: Vec<(u64, u64)> = self.counts.iter().map(|(mask, count)| (*mask, *count)).collect();
+        counts.sort_by_key(|(mask, count)| (std::cmp::Reverse(*count), *mask));
+        counts.into_iter().take(n).map(|(mask, count)| (mask, count as f64 / self.total as f64)).collect()
+    }
+}
+
+// the edge colors (top, right, bottom, left) of each symbol when they're treated like wang tiles. two symbols are
+// compatible if they could sit next to each other in some direction (i.e. one's edge matches the other's opposite edge).
+// seeded separately from the rules, so that the colors can be drawn without disturbing the rule generation.
+fn wang_edge_colors(symbol_count:u32, seed:u64) -> Vec<[u32; 4]> {
+    use rand::{SeedableRng, rngs::StdRng};
+    let mut rng = StdRng::seed_from_u64(seed.rotate_left(32));
+    let edge_color_count = (symbol_count / 2).max(2);
+    (0..symbol_count).map(|_| {
+        let mut edges = [0; 4];
+        edges.iter_mut().for_each(|e| *e = rng.gen_range(0, edge_color_count));
+        edges
+    }).collect()
+}
+
+fn wang_compatible(a:&[u32; 4], b:&[u32; 4]) -> bool {
+    (0..4).any(|side| a[side] == b[(side + 2) % 4])
+}
+
+// the outcome of running a seed, which is what fitness functions score
+#[derive(Clone, Debug)]
+pub struct RunSummary {
+    pub seed: u64,
+    pub sample_frame_count: usize,
+    pub unique_frame_count: usize,
+    pub cell_change_diff_count: u32,
+    pub active_frames: usize, // how many frames ran before the world went static (or hit the cap)
+    pub wavefront: Option<Wavefront>,
+    pub rule_count: usize, // see `World::rule_complexity`
+    pub avg_rule_length: f32,
+    #[cfg(feature="stats")]
+    pub step_latency: LatencyHistogram,
+}
+
+// how long each `step` took over a run, so that frame-time spikes (e.g. the frame where activity suddenly spreads)
+// show up instead of being averaged away
+#[cfg(feature="stats")]
+#[derive(Clone, Debug, Default)]
+pub struct LatencyHistogram {
+    samples: Vec<std::time::Duration>, // kept sorted
+}
+
+#[cfg(feature="stats")]
+impl LatencyHistogram {
+    pub fn new() -> LatencyHistogram {
+        LatencyHistogram { samples: Vec::new() }
+    }
+
+    pub fn record(&mut self, duration:std::time::Duration) {
+        let i = self.samples.binary_search(&duration).unwrap_or_else(|i| i);
+        self.samples.insert(i, duration);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    // nearest-rank percentile, e.g. `percentile(99.0)` is the p99 (None if nothing was recorded)
+    pub fn percentile(&self, p:f64) -> Option<std::time::Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let rank = (p / 100.0 * self.samples.len() as f64).ceil() as usize;
+        Some(self.samples[rank.max(1).min(self.samples.len()) - 1])
+    }
+
+    pub fn min(&self) -> Option<std::time::Duration> {
+        self.samples.first().cloned()
+    }
+
+    pub fn max(&self) -> Option<std::time::Duration> {
+        self.samples.last().cloned()
+    }
+}
+
+#[cfg(feature="stats")]
+impl std::fmt::Display for LatencyHistogram {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.samples.is_empty() {
+            return write!(f, "no steps recorded");
+        }
+        write!(f, "min: {:?}  p50: {:?}  p99: {:?}  max: {:?}  ({} steps)", self.min().unwrap(), self.percentile(50.0).unwrap(), self.percentile(99.0).unwrap(), self.max().unwrap(), self.len())
+    }
+}
+
+pub trait Fitness {
+    fn score(&self, summary:&RunSummary) -> f64;
+}
+
+// the original heuristic: every sampled frame has to be unique, and then the more the end-of-sample change masks
+// differ (i.e. the less it's just lots of little repeating oscillations) the better.
+pub struct NoveltyFitness;
+
+impl Fitness for NoveltyFitness {
+    fn score(&self, summary:&RunSummary) -> f64 {
+        if summary.unique_frame_count == summary.sample_frame_count {
+            f64::from(summary.cell_change_diff_count)
+        } else {
+            0.0
+        }
+    }
+}
+
+// wraps another fitness, taking points off for every rule and every symbol the rules need, so that of two rule sets
+// with similar behavior the smaller one wins. (the penalties are in the wrapped fitness's units.)
+pub struct RuleComplexityPenalty<F: Fitness> {
+    pub fitness: F,
+    pub per_rule: f64,
+    pub per_needed_symbol: f64,
+}
+
+impl<F: Fitness> Fitness for RuleComplexityPenalty<F> {
+    fn score(&self, summary:&RunSummary) -> f64 {
+        let needed_symbol_count = summary.rule_count as f64 * f64::from(summary.avg_rule_length);
+        self.fitness.score(summary) - self.per_rule * summary.rule_count as f64 - self.per_needed_symbol * needed_symbol_count
+    }
+}
+
+// runs both (config, seed) pairs from their seeded starting grids for the sample and compares how `fitness` scores them,
+// e.g. for picking the winner of a tournament between two rule sets. it's deterministic, so the result is reproducible.
+pub fn compare_worlds(a:(&WorldConfig, u64), b:(&WorldConfig, u64), fitness:&dyn Fitness, params:&NoveltySearchParams) -> std::cmp::Ordering {
+    let score = |(config, seed):(&WorldConfig, u64)| {
+        let mut world = World::from_config(config, seed);
+        world.randomize_from_seed(seed);
+        fitness.score(&run_headless(&mut world, seed, params, params.sample_end_frame()))
+    };
+    score(a).partial_cmp(&score(b)).unwrap_or(std::cmp::Ordering::Equal)
+}
+
+// a coarse label for how a run went, e.g. for tagging catalogued seeds so that changes in their behavior can be caught:
+// "static" if it stopped changing within the sample, "wave" if the changes kept travelling in one direction (see
+// `NoveltyTracker::wavefront`), "repetitive" if it kept going but failed the novelty heuristic, and otherwise "novel".
+pub fn classify_run(summary:&RunSummary, params:&NoveltySearchParams) -> &'static str {
+    if summary.active_frames < params.sample_end_frame() {
+        "static"
+    } else if summary.wavefront.is_some() {
+        "wave"
+    } else if NoveltyFitness.score(summary) <= params.min_end_cell_diff as f64 {
+        "repetitive"
+    } else {
+        "novel"
+    }
+}
+
+// runs `world` without a window the same way the non-interactive search does: until it goes static or reaches
+// `max_frames`, with the novelty metrics taken over the first `sample_frame_count` frames.
+pub fn run_headless(world:&mut World, seed:u64, params:&NoveltySearchParams, max_frames:usize) -> RunSummary {
+    run_headless_with_policy(world, seed, params, max_frames, None)
+}
+
+// like `run_headless`, but also stops as soon as `policy` gives up on the run (in which case `active_frames` is the
+// frame it stopped at, and the summary is one that `NoveltyFitness` scores 0)
+pub fn run_headless_with_policy(world:&mut World, seed:u64, params:&NoveltySearchParams, max_frames:usize, policy:Option<&EarlyDecisionPolicy>) -> RunSummary {
+    let mut novelty = NoveltyTracker::new(params, world.data.len());
+    let mut sampled_details = None;
+    let mut count = 0;
+    #[cfg(feature="stats")]
+    let mut step_latency = LatencyHistogram::new();
+    loop {
+        #[cfg(feature="stats")]
+        let step_start = std::time::Instant::now();
+        world.step();
+        #[cfg(feature="stats")]
+        step_latency.record(step_start.elapsed());
+        novelty.record_frame(count, world);
+        count += 1;
+        let there_were_changes = !world.is_static();
+        let aborted = policy.is_some_and(|p| p.should_abort(count, params, &novelty, world));
+        if sampled_details.is_none() && (count == params.sample_end_frame() || count == max_frames || !there_were_changes || aborted) {
+            sampled_details = Some((novelty.unique_frame_count(), novelty.cell_change_diff_count()));
+        }
+        if count == max_frames || !there_were_changes || aborted {
+            break;
+        }
+    }
+    let (unique_frame_count, cell_change_diff_count) = sampled_details.unwrap();
+    let (rule_count, avg_rule_length) = world.rule_complexity();
+    RunSummary {
+        seed,
+        sample_frame_count: params.sample_frame_count,
+        unique_frame_count,
+        cell_change_diff_count,
+        active_frames: count,
+        wavefront: novelty.wavefront(),
+        rule_count,
+        avg_rule_length,
+        #[cfg(feature="stats")]
+        step_latency,
+    }
+}
+
+fn bool_vec_diff_count(vec1:&[bool], vec2:&[bool]) -> u32 {
+    let mut diff_count = 0;
+    for (i, v) in vec1.iter().enumerate() {
+        if vec2[i] != *v { diff_count += 1; }
+    }
+    diff_count
+}
+
+// keeps the top 5, 6 and 5 bits of red, green and blue (green gets the extra bit because eyes are most sensitive to it)
+fn rgb565(r:u8, g:u8, b:u8) -> u16 {
+    (u16::from(r) >> 3) << 11 | (u16::from(g) >> 2) << 5 | u16::from(b) >> 3
+}
+
+// fnv-1a over explicitly little-endian bytes. `DefaultHasher`'s algorithm may change between rust versions (and
+// `Hash` impls write native-endian bytes), which is fine within a run but not for anything written to disk.
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> StableHasher {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write_bytes(&mut self, bytes:&[u8]) {
+        for b in bytes.iter() {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u32(&mut self, v:u32) {
+        self.write_bytes(&v.to_le_bytes());
+    }
+
+    // lengths go in before variable-length lists, so that e.g. moving an item from one list to the next changes the hash
+    fn write_len(&mut self, len:usize) {
+        self.write_bytes(&(len as u64).to_le_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// bumped whenever `World::to_rle_text`'s format changes, so that older builds refuse newer files instead of misreading them
+pub const RLE_WORLD_VERSION: u32 = 1;
+
+// the biggest world `World::from_rle_text` will make (4096x4096), so that a bad header can't ask for any amount of memory
+const MAX_RLE_WORLD_CELLS: u64 = 1 << 24;
+
+// (hue in degrees, saturation, value), the last two from 0 to 1. grays get a hue of 0.
+fn rgb_to_hsv((r, g, b):(u8, u8, u8)) -> (f32, f32, f32) {
+    let (r, g, b) = (f32::from(r), f32::from(g), f32::from(b));
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    if max == min {
+        return (0.0, 0.0, max / 255.0);
+    }
+    let h = if max == r { (g - b) / (max - min) } else if max == g { 2.0 + (b - r) / (max - min) } else { 4.0 + (r - g) / (max - min) };
+    ((h * 60.0 + 360.0) % 360.0, (max - min) / max, max / 255.0)
+}
+
+fn hsv_to_rgb((h, s, v):(f32, f32, f32)) -> (u8, u8, u8) {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let channel = |c:f32| ((c + v - s*v) * 255.0).round().clamp(0.0, 255.0) as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+// `colors` with every hue turned by `degrees` around the color wheel (saturation and brightness stay the same), e.g.
+// for animating a palette without touching the simulation
+pub fn rotate_hues(colors:&[(u8, u8, u8)], degrees:f32) -> Vec<(u8, u8, u8)> {
+    colors.iter().map(|color| {
+        let (h, s, v) = rgb_to_hsv(*color);
+        hsv_to_rgb((h + degrees, s, v))
+    }).collect()
+}
+
+pub fn calculate_vec_hash(vec: &[u32]) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut s = DefaultHasher::new();
+    vec.hash(&mut s);
+    s.finish()
+}
+
+// a "nearby" seed, for manually hill-climbing from a good one (see `mutate_seed`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeedMutation {
+    pub parent: u64,
+    pub index: u64, // the nth mutation of `parent`
+    pub flipped_bits: Vec<u32>,
+    pub child: u64,
+}
+
+impl std::fmt::Display for SeedMutation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let bits: Vec<String> = self.flipped_bits.iter().map(|b| b.to_string()).collect();
+        write!(f, "mutation #{} of {} (flipped bits {})", self.index, self.parent, bits.join(", "))
+    }
+}
+
+// flips `bit_count` distinct bits of `parent`. which bits only depends on `parent` and `index`, so a chain of mutations
+// can be retraced from the parent seeds and indices alone. nearby seeds don't always give related dynamics, but
+// sometimes they do.
+pub fn mutate_seed(parent:u64, index:u64, bit_count:u32) -> SeedMutation {
+    use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+    assert!((1..=64).contains(&bit_count), "Bit count must be between 1 and 64.");
+    let mut rng = StdRng::seed_from_u64(parent ^ index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    let mut bits: Vec<u32> = (0..64).collect();
+    bits.shuffle(&mut rng);
+    let mut flipped_bits = bits[..bit_count as usize].to_vec();
+    flipped_bits.sort();
+    let child = flipped_bits.iter().fold(parent, |seed, bit| seed ^ (1 << bit));
+    SeedMutation { parent, index, flipped_bits, child }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Divergence {
+    pub frame: usize, // 0 is the starting grid
+    pub differing_cells: usize,
+}
+
+// runs `seed` twice from scratch (grid included, see `randomize_from_seed`) and compares the `checksum` of the two
+// worlds after every step, returning the first frame where they differ. with the `parallel` feature one of the runs is
+// stepped on rayon's threadpool and the other sequentially, so that thread scheduling is covered too. a build that
+// returns `None` here can be trusted to reproduce a seed.
+pub fn replay_divergence(config:&WorldConfig, seed:u64, frame_count:usize) -> Option<Divergence> {
+    let start = |parallel| {
+        let mut world = World::from_config(config, seed);
+        world.randomize_from_seed(seed);
+        (world, parallel)
+    };
+    let mut runs = [start(cfg!(feature="parallel")), start(false)];
+    for frame in 0..=frame_count {
+        if frame > 0 {
+            runs.iter_mut().for_each(|(world, parallel)| world.step_within(None, *parallel));
+        }
+        let (a, b) = (&runs[0].0, &runs[1].0);
+        if a.checksum() != b.checksum() {
+            let differing_cells = a.data.iter().zip(b.data.iter()).filter(|(x, y)| x != y).count();
+            return Some(Divergence { frame, differing_cells });
+        }
+    }
+    None
+}
+
+// aggregate results of running a batch of seeds under one set of generation parameters (see `parameter_sweep`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepCell {
+    pub symbol_count: u32,
+    pub avg_symbols_per_rule: f32,
+    pub seed_count: usize,
+    pub interesting_fraction: f64, // of the seeds that `NoveltyFitness` scored above `min_end_cell_diff`
+    pub mean_entropy: f64, // `symbol_entropy` at the end of each run
+    pub mean_active_frames: f64,
+}
+
+impl SweepCell {
+    pub const CSV_HEADER: &'static str = "symbol_count,avg_symbols_per_rule,seed_count,interesting_fraction,mean_entropy,mean_active_frames";
+
+    pub fn to_csv_row(&self) -> String {
+        format!("{},{},{},{:.4},{:.4},{:.1}", self.symbol_count, self.avg_symbols_per_rule, self.seed_count, self.interesting_fraction, self.mean_entropy, self.mean_active_frames)
+    }
+}
+
+// runs `seeds` (each from its seeded starting grid) under every (symbol count, average symbols per rule) pair in
+// `params_grid`, with the rest of `config` fixed, to map out which parameters give interesting worlds. the pairs are
+// spread over rayon's threadpool with the `parallel` feature. pairs that `check_generation_params` rejects should be
+// filtered out first.
+pub fn parameter_sweep(config:&WorldConfig, params_grid:&[(u32, f32)], seeds:&[u64], params:&NoveltySearchParams, max_frames:usize) -> Vec<SweepCell> {
+    let run_cell = |(symbol_count, avg_symbols_per_rule):&(u32, f32)| {
+        let config = WorldConfig { symbol_count: *symbol_count, avg_symbols_per_rule: *avg_symbols_per_rule, ..config.clone() };
+        let (mut interesting, mut entropy, mut active_frames) = (0, 0.0, 0);
+        for seed in seeds.iter() {
+            let mut world = World::from_config(&config, *seed);
+            world.randomize_from_seed(*seed);
+            let summary = run_headless(&mut world, *seed, params, max_frames);
+            if NoveltyFitness.score(&summary) > params.min_end_cell_diff as f64 {
+                interesting += 1;
+            }
+            entropy += world.symbol_entropy();
+            active_frames += summary.active_frames;
+        }
+        let n = seeds.len().max(1) as f64;
+        SweepCell { symbol_count: *symbol_count, avg_symbols_per_rule: *avg_symbols_per_rule, seed_count: seeds.len(), interesting_fraction: interesting as f64 / n, mean_entropy: entropy / n, mean_active_frames: active_frames as f64 / n }
+    };
+    #[cfg(feature="parallel")]
+    return params_grid.par_iter().map(run_cell).collect();
+    #[cfg(not(feature="parallel"))]
+    return params_grid.iter().map(run_cell).collect();
+}
+
+// how sensitive a rule set is to its precedence: runs the world and a rule-shuffled copy of it from the same initial
+// grid (seeded, like the rules, by `seed`) for `frame_count` steps, and returns the fraction of cells that differ at
+// the end.
+pub fn rule_order_divergence(config:&WorldConfig, seed:u64, shuffle_seed:u64, frame_count:usize) -> f32 {
+    let mut original = World::from_config(config, seed);
+    original.randomize_from_seed(seed);
+    let mut shuffled = World::from_config(config, seed);
+    shuffled.data = original.data.clone();
+    shuffled.shuffle_rules(shuffle_seed);
+    for _ in 0..frame_count {
+        original.step();
+        shuffled.step();
+    }
+    let diff_count = original.data.iter().zip(shuffled.data.iter()).filter(|(a, b)| a != b).count();
+    diff_count as f32 / original.data.len() as f32
+}
+
+// re-runs a world from `initial_data` (the grid the run being summarized started from) for `frame_count` steps and
+// saves `sample_count` evenly-spaced frames (including the first and last), each scaled to `thumb_size` square, side
+// by side in a single png.
+pub fn render_thumbnail_strip(config:&WorldConfig, seed:u64, initial_data:&[u32], frame_count:usize, sample_count:usize, thumb_size:u32, out:&std::path::Path) -> Result<(), WorldError> {
+    assert!(sample_count > 0 && thumb_size > 0);
+    let mut world = World::from_config(config, seed);
+    assert_eq!(initial_data.len(), world.data.len(), "The starting grid must be the same size as the world.");
+    world.data = initial_data.to_vec();
+
+    let mut frame_buffer = vec![];
+    let mut strip = image::RgbImage::new(thumb_size * sample_count as u32, thumb_size);
+    let mut frame = 0;
+    for sample in 0..sample_count {
+        let sample_frame = if sample_count == 1 { frame_count } else { sample * frame_count / (sample_count - 1) };
+        while frame < sample_frame {
+            world.step();
+            frame += 1;
+        }
+        let full = world.to_rgb_image(&mut frame_buffer, 1);
+        let thumb = image::imageops::resize(&full, thumb_size, thumb_size, image::FilterType::Nearest);
+        image::imageops::replace(&mut strip, &thumb, sample as u32 * thumb_size, 0);
+    }
+    strip.save(out)?;
+    Ok(())
+}
+
+pub fn rle_encode(data: &[u32]) -> Vec<(u32, u32)> {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for v in data.iter() {
+        match runs.last_mut() {
+            Some((symbol, run_length)) if *symbol == *v => *run_length += 1,
+            _ => runs.push((*v, 1)),
+        }
+    }
+    runs
+}
+
+pub fn rle_decode(runs: &[(u32, u32)]) -> Vec<u32> {
+    let mut data = Vec::with_capacity(runs.iter().map(|(_, n)| *n as usize).sum());
+    for (symbol, run_length) in runs.iter() {
+        data.extend(std::iter::repeat(*symbol).take(*run_length as usize));
+    }
+    data
+}
+
+// grid files are the world size on the first line and then one `<symbol> <run length>` run per line (see `rle_encode`).
+// unlike a seed, they reproduce a grid exactly regardless of how `randomize` works.
+pub fn write_grid<P: AsRef<std::path::Path>>(path:P, world_size:u32, data:&[u32]) -> Result<(), WorldError> {
+    let mut text = format!("{}\n", world_size);
+    for (symbol, run_length) in rle_encode(data) {
+        text.push_str(&format!("{} {}\n", symbol, run_length));
+    }
+    std::fs::write(path, text)?;
+    Ok(())
+}
+
+// palette files are one `#rrggbb` color per line, in symbol order (the `#` is optional when reading)
+pub fn write_palette<P: AsRef<std::path::Path>>(path:P, colors:&[(u8, u8, u8)]) -> Result<(), WorldError> {
+    let text: String = colors.iter().map(|(r, g, b)| format!("#{:02x}{:02x}{:02x}\n", r, g, b)).collect();
+    std::fs::write(path, text)?;
+    Ok(())
+}
+
+pub fn read_palette<P: AsRef<std::path::Path>>(path:P) -> Result<Vec<(u8, u8, u8)>, WorldError> {
+    let text = std::fs::read_to_string(path)?;
+    text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).map(|l| {
+        let hex = l.trim_start_matches('#');
+        let channel = |i:usize| hex.get(i..i+2).and_then(|c| u8::from_str_radix(c, 16).ok());
+        match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok((r, g, b)),
+            _ => Err(WorldError::Palette(format!("invalid color: {}", l))),
+        }
+    }).collect()
+}
+
+// if `caption_seed` is given then each frame gets "SEED <seed> FRAME <index>" drawn in its top-left corner.
+// `upscale` is the size of the block of pixels each cell is drawn as (1 for one pixel per cell).
+pub fn make_gif_from_frames(frames: &[Vec<u32>], colors: &[(u8,u8,u8)], filename:&str, caption_seed:Option<u64>, upscale:u32) {
+    let world_size = (frames[0].len() as f32).sqrt() as u32;
+    let mut recorder = GifRecorder::create_upscaled(format!("./gifs/{}.gif", filename), world_size, colors, caption_seed, upscale).unwrap();
+    for frame_data in frames.iter() {
+        recorder.add_frame(frame_data).unwrap();
+    }
+}
+
+// a gif that's written one frame at a time and stays open until it's dropped, so that a recording can be paused and
+// continued (e.g. with `--append-gif`) without holding every frame in memory or starting a new file
+pub struct GifRecorder {
+    encoder: gif::Encoder<std::fs::File>,
+    world_size: u32,
+    upscale: u32, // pixels per cell along each side
+    caption: Option<(u64, u8, u8)>, // the seed, and the palette indices of the caption's background and text
+    frame_count: usize,
+}
+
+impl GifRecorder {
+    pub fn create<P: AsRef<std::path::Path>>(path:P, world_size:u32, colors:&[(u8,u8,u8)], caption_seed:Option<u64>) -> Result<GifRecorder, WorldError> {
+        GifRecorder::create_upscaled(path, world_size, colors, caption_seed, 1)
+    }
+
+    // like `create`, but each cell is drawn as an upscale x upscale block, so the gif is (world_size * upscale) pixels
+    // across rather than needing to be scaled up afterwards (which usually blurs it)
+    pub fn create_upscaled<P: AsRef<std::path::Path>>(path:P, world_size:u32, colors:&[(u8,u8,u8)], caption_seed:Option<u64>, upscale:u32) -> Result<GifRecorder, WorldError> {
+        use gif::{Encoder, Repeat, SetParameter};
+
+        assert!(upscale > 0, "The upscale factor must be positive.");
+        let image_size = world_size * upscale;
+        assert!(image_size <= u32::from(u16::MAX), "The upscaled gif must be at most 65535 pixels across.");
+
+        let mut flat_colors: Vec<u8> = Vec::with_capacity(colors.len()*3);
+        for (r, g, b) in colors.iter() {
+            flat_colors.push(*r);
+            flat_colors.push(*g);
+            flat_colors.push(*b);
+        }
+        // two extra palette entries for the caption's background and text, which have to fit in the gif's 256 colors:
+        let caption = match caption_seed {
+            Some(_) if colors.len() + 2 > 256 => return Err(WorldError::Palette(format!("a captioned gif only has room for 254 colors, but the palette has {}", colors.len()))),
+            Some(seed) => {
+                flat_colors.extend_from_slice(&[0, 0, 0, 255, 255, 255]);
+                Some((seed, colors.len() as u8, colors.len() as u8 + 1))
+            }
+            None => None,
+        };
+
+        let image = std::fs::File::create(path)?;
+        let mut encoder = Encoder::new(image, image_size as u16, image_size as u16, &flat_colors[..])?;
+        encoder.set(Repeat::Infinite)?;
+        Ok(GifRecorder { encoder, world_size, upscale, caption, frame_count: 0 })
+    }
+
+    pub fn add_frame(&mut self, data:&[u32]) -> Result<(), WorldError> {
+        use std::borrow::Cow;
+
+        assert_eq!(data.len(), (self.world_size * self.world_size) as usize, "The frame must be the same size as the recording.");
+        let image_size = (self.world_size * self.upscale) as usize;
+        let mut u8_frame_data: Vec<u8> = if self.upscale == 1 {
+            data.iter().map(|x| *x as u8).collect()
+        } else {
+            let mut pixels = vec![0; image_size * image_size];
+            fill_cell_blocks(&mut pixels, image_size, image_size, self.world_size as usize, self.upscale as usize, |i| data[i] as u8);
+            pixels
+        };
+        if let Some((seed, background, text)) = self.caption {
+            let caption = format!("SEED {} FRAME {}", seed, self.frame_count);
+            draw_caption(&mut u8_frame_data, image_size, &caption, text, background);
+        }
+        let frame = gif::Frame {
+            width: image_size as u16,
+            height: image_size as u16,
+            buffer: Cow::Borrowed(&u8_frame_data[..]),
+            ..gif::Frame::default()
+        };
+        self.encoder.write_frame(&frame)?;
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+}
+
+// records exactly the frames between a start and a stop marker (the [ and ] keys in interactive mode), rather than
+// however many of the last frames happen to be kept around. each start begins a new gif, which is finished by the stop.
+#[derive(Default)]
+pub struct MarkerRecorder {
+    recorder: Option<GifRecorder>,
+}
+
+impl MarkerRecorder {
+    pub fn new() -> MarkerRecorder {
+        MarkerRecorder { recorder: None }
+    }
+
+    // frames passed to `add_frame` from now on go into a new gif at `path` (does nothing if already recording)
+    pub fn start<P: AsRef<std::path::Path>>(&mut self, path:P, world_size:u32, colors:&[(u8,u8,u8)], caption_seed:Option<u64>, upscale:u32) -> Result<(), WorldError> {
+        if self.recorder.is_none() {
+            self.recorder = Some(GifRecorder::create_upscaled(path, world_size, colors, caption_seed, upscale)?);
+        }
+        Ok(())
+    }
+
+    // finishes the gif, returning how many frames went into it (`None` if it wasn't recording)
+    pub fn stop(&mut self) -> Option<usize> {
+        self.recorder.take().map(|recorder| recorder.frame_count())
+    }
+
+    // to be called with every frame. only the ones between the markers are kept.
+    pub fn add_frame(&mut self, data:&[u32]) -> Result<(), WorldError> {
+        match self.recorder.as_mut() {
+            Some(recorder) => recorder.add_frame(data),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    // frames recorded since the start marker
+    pub fn frame_count(&self) -> usize {
+        self.recorder.as_ref().map_or(0, |recorder| recorder.frame_count())
+    }
+}
+
+
+// writes each frame it's given into `dir` as its own png (`frame_00000.png`, `frame_00001.png`, ...), e.g. for making
+// a video with `ffmpeg -i dir/frame_%05d.png`, which doesn't have the 256 color limit of gifs
+pub struct PngFrameWriter {
+    dir: std::path::PathBuf,
+    upscale: u32,
+    digits: usize,
+    frame_count: usize,
+    buffer: Vec<u32>,
+}
+
+impl PngFrameWriter {
+    // `total_frames` is how many frames will (at most) be written, so that the numbers can be padded to sort correctly
+    pub fn new<P: AsRef<std::path::Path>>(dir:P, upscale:u32, total_frames:usize) -> Result<PngFrameWriter, WorldError> {
+        assert!(upscale > 0, "The upscale factor must be positive.");
+        std::fs::create_dir_all(&dir)?;
+        Ok(PngFrameWriter { dir: dir.as_ref().to_path_buf(), upscale, digits: png_frame_digits(total_frames), frame_count: 0, buffer: vec![] })
+    }
+
+    pub fn write_frame(&mut self, world:&World) -> Result<(), WorldError> {
+        let path = self.dir.join(png_frame_filename(self.frame_count, self.digits));
+        world.to_rgb_image(&mut self.buffer, self.upscale).save(path)?;
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+}
+
+// enough digits for the last frame's number, and at least 5 (so that `%05d` works for any run that fits in that)
+fn png_frame_digits(total_frames:usize) -> usize {
+    total_frames.saturating_sub(1).to_string().len().max(5)
+}
+
+fn png_frame_filename(index:usize, digits:usize) -> String {
+    format!("frame_{:0width$}.png", index, width = digits)
+}
+
+// 3x5 pixel glyphs (one row per byte, most significant of the 3 bits on the left) for the few characters captions need:
+fn caption_glyph(c:char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' | 'S' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b111, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b111, 0b100, 0b100],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        _ => [0; 5],
+    }
+}
+
+// draws `text` onto a box of `bg` in the top-left corner of an indexed frame (clipped to the frame), and returns the
+// (width, height) of that box.
+fn draw_caption(frame:&mut [u8], frame_width:usize, text:&str, fg:u8, bg:u8) -> (usize, usize) {
+    let frame_height = frame.len() / frame_width;
+    let box_width = (1 + text.chars().count()*4).min(frame_width);
+    let box_height = 7.min(frame_height);
+    for y in 0..box_height {
+        for x in 0..box_width {
+            frame[y*frame_width + x] = bg;
+        }
+    }
+    for (ci, c) in text.chars().enumerate() {
+        for (gy, row) in caption_glyph(c).iter().enumerate() {
+            for gx in 0..3 {
+                let (x, y) = (1 + ci*4 + gx, 1 + gy);
+                if row & (0b100 >> gx) != 0 && x < box_width && y < box_height {
+                    frame[y*frame_width + x] = fg;
+                }
+            }
+        }
+    }
+    (box_width, box_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_world() {
+        //let mut w = World::new(4);
+    }
+
+    #[test]
+    fn test_seed_from_image() {
+        let mut world = World::new(8, 5, 2.0, 1);
+        let (r, g, b) = world.symbol_to_color[3];
+        // deliberately a different size to the world so that resampling kicks in:
+        let img = image::RgbImage::from_pixel(5, 3, image::Rgb([r, g, b]));
+        world.seed_from_rgb_image(&img);
+        assert!(world.data.iter().all(|v| *v == 3));
+    }
+
+    #[test]
+    fn test_erosion_matches_radius() {
+        let world_size = 8;
+        for radius in 1..3 {
+            let mut cell_changed_flags = vec![false; 64];
+            let mut neighborhood_changed_flags = vec![false; 64];
+            // a single change right at the edge, so the wrap-around is exercised too:
+            cell_changed_flags[7] = true; // (7, 0)
+            erode_change_flags(&cell_changed_flags, &mut neighborhood_changed_flags, world_size, radius);
+            for y in 0..world_size {
+                for x in 0..world_size {
+                    let dx = (x as i32 - 7).abs().min(world_size as i32 - (x as i32 - 7).abs());
+                    let dy = (y as i32).min(world_size as i32 - y as i32);
+                    let within_radius = dx <= radius as i32 && dy <= radius as i32;
+                    assert_eq!(neighborhood_changed_flags[(y*world_size + x) as usize], within_radius, "({}, {}) radius {}", x, y, radius);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_motion_gated_rule() {
+        let mut world = World::new(8, 3, 1.0, 1);
+        world.rule_phases = vec![vec![WorldRule { symbols_needed: vec![0], output_symbol: 1, requires_motion: true, min_count: 1 }]];
+        world.data.iter_mut().for_each(|v| *v = 0);
+        // pretend that only (4, 4) changed last step:
+        world.data[4*8 + 4] = 2;
+        world.cell_changed_flags.iter_mut().for_each(|v| *v = false);
+        world.cell_changed_flags[4*8 + 4] = true;
+        erode_change_flags(&world.cell_changed_flags, &mut world.neighborhood_changed_flags, 8, 1);
+        world.step();
+        for y in 0..8 {
+            for x in 0..8 {
+                let adjacent = (x as i32 - 4).abs() <= 1 && (y as i32 - 4).abs() <= 1;
+                assert_eq!(world.data[y*8 + x] == 1, adjacent, "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rle_round_trip() {
+        let mut world = World::new(16, 5, 2.0, 1);
+        world.randomize();
+        assert_eq!(rle_decode(&world.to_rle()), world.data);
+
+        // structured: horizontal stripes, so each row is a single run
+        world.data.iter_mut().enumerate().for_each(|(i, v)| *v = (i as u32 / 16) % 2);
+        let runs = world.to_rle();
+        assert_eq!(runs.len(), 16);
+        assert!(runs.iter().all(|(_, n)| *n == 16));
+        assert_eq!(rle_decode(&runs), world.data);
+
+        assert!(rle_encode(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_boundary_energy() {
+        let mut world = World::new(8, 3, 1.0, 1);
+        world.data.iter_mut().for_each(|v| *v = 2);
+        assert_eq!(world.boundary_energy(), 0);
+        world.data.iter_mut().enumerate().for_each(|(i, v)| *v = ((i % 8) + (i / 8)) as u32 % 2);
+        assert_eq!(world.boundary_energy(), 2 * 64);
+    }
+
+    #[test]
+    fn test_complexity_score_ordering() {
+        let mut world = World::new(64, 5, 2.0, 1);
+        world.randomize();
+        let noise = world.complexity_score();
+
+        // 16x16 blocks of random symbols:
+        let noise_data = world.data.clone();
+        world.data.iter_mut().enumerate().for_each(|(i, v)| *v = noise_data[(i / 64 / 16) * 16 * 64 + (i % 64 / 16) * 16]);
+        let structure = world.complexity_score();
+
+        world.data.iter_mut().for_each(|v| *v = 1);
+        let uniform = world.complexity_score();
+
+        assert!(noise > structure, "{} > {}", noise, structure);
+        assert!(structure > uniform, "{} > {}", structure, uniform);
+    }
+
+    #[test]
+    fn test_caption_only_touches_caption_region() {
+        let frame = vec![3u8; 64*64];
+        let mut captioned = frame.clone();
+        let (box_width, box_height) = draw_caption(&mut captioned, 64, "SEED 42 FRAME 7", 5, 6);
+        assert_ne!(frame, captioned);
+        for y in 0..64 {
+            for x in 0..64 {
+                if x >= box_width || y >= box_height {
+                    assert_eq!(frame[y*64 + x], captioned[y*64 + x], "({}, {})", x, y);
+                }
+            }
+        }
+
+        // the caption's two colors go after the palette's, so they need room in the gif's 256:
+        let path = std::env::temp_dir().join("color_rules_test_caption_palette.gif");
+        let colors = vec![(1, 2, 3); 255];
+        match GifRecorder::create(&path, 4, &colors, Some(42)) {
+            Err(WorldError::Palette(message)) => assert!(message.contains("255"), "{}", message),
+            _ => panic!("expected a palette error"),
+        }
+        assert!(GifRecorder::create(&path, 4, &colors, None).is_ok());
+        assert!(GifRecorder::create(&path, 4, &colors[..254], Some(42)).is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_step_region_freezes_outside() {
+        let mut world = World::new(16, 5, 2.0, 1);
+        world.randomize();
+        let before = world.data.clone();
+        let rect = Rect { x: 4, y: 4, width: 6, height: 6 };
+        for _ in 0..10 {
+            world.step_region(rect);
+        }
+        for y in 0..16 {
+            for x in 0..16 {
+                if !rect.contains((x, y)) {
+                    assert_eq!(world.data[(y*16 + x) as usize], before[(y*16 + x) as usize]);
+                    // still pending, so that a later full step re-evaluates it:
+                    assert!(world.neighborhood_changed_flags[(y*16 + x) as usize]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_locked_symbol_never_changes() {
+        let mut world = World::new(8, 3, 1.0, 1);
+        world.rule_phases = vec![vec![
+            WorldRule { symbols_needed: vec![0], output_symbol: 1, requires_motion: false, min_count: 1 },
+            WorldRule { symbols_needed: vec![1], output_symbol: 2, requires_motion: false, min_count: 1 },
+            WorldRule { symbols_needed: vec![2], output_symbol: 0, requires_motion: false, min_count: 1 },
+        ]];
+        world.randomize();
+        world.data[3*8 + 5] = 2;
+        world.locked_symbols.insert(2);
+        for _ in 0..20 {
+            world.step();
+            assert_eq!(world.data[3*8 + 5], 2);
+        }
+    }
+
+    #[test]
+    fn test_iter_frames() {
+        let mut world = World::new(16, 5, 2.0, 1);
+        world.randomize();
+        let frames: Vec<FrameView> = world.iter_frames().take(20).collect();
+        assert_eq!(frames.len(), 20);
+        for (i, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.index, i);
+            assert_eq!(frame.histogram.iter().sum::<u32>(), 16*16);
+        }
+    }
+
+    #[test]
+    fn test_render_thumbnail_strip() {
+        let config = WorldConfig { size: 16, symbol_count: 5, avg_symbols_per_rule: 2.0, transition_mode: TransitionMode::Rules, repulsion: false, edge_matching: false };
+        let out = std::env::temp_dir().join("color_rules_test_thumbnail_strip.png");
+        let mut world = World::from_config(&config, 1);
+        world.randomize_from_seed(1);
+        render_thumbnail_strip(&config, 1, &world.data, 20, 4, 32, &out).unwrap();
+        let strip = image::open(&out).unwrap().to_rgb();
+        assert_eq!(strip.dimensions(), (4*32, 32));
+        // the same seed and starting grid always give the same strip:
+        let again = std::env::temp_dir().join("color_rules_test_thumbnail_strip_again.png");
+        render_thumbnail_strip(&config, 1, &world.data, 20, 4, 32, &again).unwrap();
+        assert_eq!(std::fs::read(&out).unwrap(), std::fs::read(&again).unwrap());
+        std::fs::remove_file(&out).unwrap();
+        std::fs::remove_file(&again).unwrap();
+    }
+
+    #[test]
+    fn test_last_changed_tracking() {
+        let mut world = World::new(16, 5, 2.0, 1);
+        world.randomize();
+        world.track_last_changed();
+        world.step();
+        assert!(world.cell_changed_flags.iter().any(|v| *v));
+        for _ in 0..2 {
+            world.step();
+        }
+        let last_changed = world.last_changed.as_ref().unwrap();
+        for (i, changed) in world.cell_changed_flags.iter().enumerate() {
+            if *changed {
+                assert_eq!(last_changed[i], 3);
+            } else {
+                assert!(last_changed[i] < 3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_majority_transition() {
+        let mut world = World::new(4, 3, 1.0, 1);
+        world.transition_mode = TransitionMode::Majority;
+        let data = vec![
+            2, 2, 0, 1,
+            2, 0, 2, 1,
+            1, 2, 0, 1,
+            1, 1, 1, 1,
+        ];
+        world.data = data.clone();
+        world.step();
+        // (1, 1) sees five 2s, three 0s and a 1:
+        assert_eq!(world.data[4 + 1], 2);
+        assert_eq!(majority_symbol(&data, 4, 1, (1, 1)), 2);
+    }
+
+    #[test]
+    fn test_shuffle_rules_is_deterministic() {
+        let mut a = World::new(8, 5, 2.0, 1);
+        let mut b = World::new(8, 5, 2.0, 1);
+        let original = a.rule_phases.clone();
+        a.shuffle_rules(42);
+        b.shuffle_rules(42);
+        assert_eq!(a.rule_phases, b.rule_phases);
+        assert_ne!(a.rule_phases, original);
+
+        let config = WorldConfig { size: 16, symbol_count: 5, avg_symbols_per_rule: 2.0, transition_mode: TransitionMode::Rules, repulsion: false, edge_matching: false };
+        let divergence = rule_order_divergence(&config, 1, 42, 10);
+        assert!((0.0..=1.0).contains(&divergence));
+        // the same arguments always give the same answer:
+        for _ in 0..3 {
+            assert_eq!(rule_order_divergence(&config, 1, 42, 10), divergence);
+        }
+    }
+
+    #[test]
+    fn test_count_and_present_symbols() {
+        let mut world = World::new(2, 5, 2.0, 1);
+        world.data = vec![4, 1, 4, 4];
+        assert_eq!(world.count_symbol(4), 3);
+        assert_eq!(world.count_symbol(1), 1);
+        assert_eq!(world.count_symbol(0), 0);
+        assert_eq!(world.present_symbols(), vec![1, 4]);
+        assert_eq!(world.histogram(), vec![0, 1, 0, 0, 3]);
+    }
+
+    #[test]
+    fn test_run_headless() {
+        let params = NoveltySearchParams { sample_frame_count: 50, min_end_cell_diff: 25, change_window: 5, change_window_gap: 0, burn_in_frames: 0 };
+        let mut world = World::new(16, 5, 2.0, 1);
+        world.data.iter_mut().for_each(|v| *v = 0);
+        world.rule_phases = vec![vec![WorldRule { symbols_needed: vec![1], output_symbol: 2, requires_motion: false, min_count: 1 }]];
+        // nothing can ever change, so it goes static straight away and isn't novel:
+        let summary = run_headless(&mut world, 1, &params, 100);
+        assert_eq!(summary.active_frames, 1);
+        assert_eq!(summary.unique_frame_count, 1);
+        assert_eq!(NoveltyFitness.score(&summary), 0.0);
+    }
+
+    #[test]
+    fn test_symmetry_score() {
+        let mut world = World::new(32, 5, 2.0, 1);
+        world.randomize();
+        let random = world.symmetry_score();
+        assert!(random.horizontal < 0.35 && random.vertical < 0.35 && random.rotational < 0.35, "{:?}", random);
+
+        // mirror the top-left quadrant into the others, about an axis that's off-center (so it has to wrap):
+        let original = world.data.clone();
+        for y in 0..32u32 {
+            for x in 0..32u32 {
+                let mx = if x < 16 { x } else { 31 - x };
+                let my = if y < 16 { y } else { 31 - y };
+                world.data[(((y + 5) % 32)*32 + (x + 9) % 32) as usize] = original[(my*32 + mx) as usize];
+            }
+        }
+        let symmetric = world.symmetry_score();
+        assert_eq!(symmetric, SymmetryScore { horizontal: 1.0, vertical: 1.0, rotational: 1.0 });
+    }
+
+    #[test]
+    fn test_repulsion_disrupts_saturated_region() {
+        let config = WorldConfig { size: 16, symbol_count: 5, avg_symbols_per_rule: 2.0, transition_mode: TransitionMode::Rules, repulsion: true, edge_matching: false };
+        let mut world = World::from_config(&config, 1);
+        assert_eq!(world.rule_phases[0].len(), World::from_config(&WorldConfig { repulsion: false, ..config.clone() }, 1).rule_phases[0].len() + 5);
+        // a completely monopolized grid:
+        world.data.iter_mut().for_each(|v| *v = 0);
+        assert_eq!(world.dominant_fraction(), 1.0);
+        world.step();
+        assert_eq!(world.count_symbol(0), 0);
+        // whereas a mixed grid is left alone by the repulsion rules:
+        let mut world = World::from_config(&config, 1);
+        world.rule_phases[0].truncate(5);
+        world.data.iter_mut().enumerate().for_each(|(i, v)| *v = (i as u32 + i as u32 / 16) % 2);
+        let before = world.data.clone();
+        world.step();
+        assert_eq!(world.data, before);
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        let mut world = World::new(4, 3, 1.0, 1);
+        world.data = vec![
+            0, 0, 1, 1,
+            0, 2, 2, 1,
+            0, 0, 0, 0,
+            2, 2, 2, 2,
+        ];
+        assert_eq!(world.to_ascii(), "0011\n0221\n0000\n2222\n");
+        // past `Z`, symbols wrap back around:
+        let mut world = World::new(2, 64, 1.0, 1);
+        world.data = vec![10, 61, 62, 63];
+        assert_eq!(world.to_ascii(), "aZ\n01\n");
+    }
+
+    #[cfg(feature="stats")]
+    #[test]
+    fn test_latency_histogram() {
+        use std::time::Duration;
+        let mut histogram = LatencyHistogram::new();
+        assert_eq!(histogram.percentile(50.0), None);
+        for ms in (1..=100).rev() {
+            histogram.record(Duration::from_millis(ms));
+        }
+        assert_eq!(histogram.min(), Some(Duration::from_millis(1)));
+        assert_eq!(histogram.percentile(50.0), Some(Duration::from_millis(50)));
+        assert_eq!(histogram.percentile(99.0), Some(Duration::from_millis(99)));
+        assert_eq!(histogram.max(), Some(Duration::from_millis(100)));
+
+        let mut world = World::new(16, 5, 2.0, 1);
+        world.randomize();
+        let summary = run_headless(&mut world, 1, &NoveltySearchParams { sample_frame_count: 20, min_end_cell_diff: 25, change_window: 5, change_window_gap: 0, burn_in_frames: 0 }, 20);
+        assert_eq!(summary.step_latency.len(), summary.active_frames);
+    }
+
+    #[test]
+    fn test_change_window_catches_slow_oscillator() {
+        // a single "light" that moves around a loop of 7 cells, i.e. a period-7 oscillator:
+        let run = |change_window| {
+            let params = NoveltySearchParams { sample_frame_count: 100, min_end_cell_diff: 0, change_window, change_window_gap: 0, burn_in_frames: 0 };
+            let mut world = World::new(8, 7, 1.0, 1);
+            let mut novelty = NoveltyTracker::new(&params, world.data.len());
+            for count in 0..=params.sample_frame_count {
+                world.cell_changed_flags.iter_mut().enumerate().for_each(|(i, v)| *v = i < 7 && i == count % 7);
+                world.data[63] = count as u32; // every frame is unique, as far as the hashes are concerned
+                novelty.record_frame(count, &world);
+            }
+            novelty.cell_change_diff_count()
+        };
+        // the default 5-frame windows each only see part of the cycle (so it looks novel)...
+        assert!(run(5) > 0);
+        // ...but windows that span a whole period see the same cells change, exposing it as a false positive:
+        assert_eq!(run(7), 0);
+        assert_eq!(run(14), 0);
+    }
+
+    #[test]
+    fn test_rule_phases_alternate() {
+        let world = World::new_with_phases(8, 3, 1.0, 1, 2);
+        assert_eq!(world.rule_phases.len(), 2);
+        assert_eq!(world.rule_phases[0], World::new(8, 3, 1.0, 1).rule_phases[0]);
+
+        let mut world = World::new(8, 3, 1.0, 1);
+        world.rule_phases = vec![
+            vec![WorldRule { symbols_needed: vec![0], output_symbol: 1, requires_motion: false, min_count: 1 }],
+            vec![WorldRule { symbols_needed: vec![1], output_symbol: 0, requires_motion: false, min_count: 1 }],
+        ];
+        world.data.iter_mut().for_each(|v| *v = 0);
+        for _ in 0..6 {
+            world.step();
+            // odd frames come from the first phase and even frames from the second:
+            let expected = if world.frame % 2 == 1 { 1 } else { 0 };
+            assert!(world.data.iter().all(|v| *v == expected), "frame {}", world.frame);
+        }
+    }
+
+    #[test]
+    fn test_rule_mask() {
+        let mut world = World::new(8, 3, 1.0, 1);
+        world.rule_phases = vec![vec![
+            WorldRule { symbols_needed: vec![2], output_symbol: 0, requires_motion: false, min_count: 1 },
+            WorldRule { symbols_needed: vec![0], output_symbol: 1, requires_motion: false, min_count: 1 },
+        ]];
+        world.data.iter_mut().for_each(|v| *v = 0);
+        // the second rule is the only one that matches, so with it masked out nothing changes:
+        world.apply_rule_mask(&[true, false]);
+        world.step();
+        assert_eq!(world.count_symbol(0), 64);
+        // and re-enabling it brings it back, with its precedence intact:
+        world.apply_rule_mask(&[true, true]);
+        world.step();
+        assert_eq!(world.count_symbol(1), 64);
+    }
+
+    #[test]
+    #[should_panic(expected = "one entry per rule")]
+    fn test_rule_mask_length_is_checked() {
+        let mut world = World::new(8, 3, 1.0, 1);
+        let rule_count = world.rule_phases[0].len();
+        world.apply_rule_mask(&vec![true; rule_count + 1]);
+    }
+
+    #[test]
+    fn test_rules_to_dot() {
+        let mut world = World::new(8, 3, 1.0, 1);
+        world.rule_phases = vec![vec![
+            WorldRule { symbols_needed: vec![0, 2], output_symbol: 1, requires_motion: false, min_count: 1 },
+            WorldRule { symbols_needed: vec![1], output_symbol: 2, requires_motion: false, min_count: 1 },
+        ]];
+        let dot = world.rules_to_dot();
+        assert!(dot.starts_with("digraph rules {"));
+        assert_eq!(dot.lines().filter(|l| l.contains("fillcolor")).count(), 3);
+        let edges: Vec<&str> = dot.lines().filter(|l| l.contains("->")).map(|l| l.trim()).collect();
+        assert_eq!(edges, vec!["0 -> 1 [label=\"0\"];", "2 -> 1 [label=\"0\"];", "1 -> 2 [label=\"1\"];"]);
+    }
+
+    #[test]
+    fn test_behavior_fingerprint_ignores_symbol_identity() {
+        let mut world = World::new(32, 5, 2.0, 1);
+        world.randomize_from_seed(1);
+
+        // the exact same world, but with every symbol renamed (in the grid and in the rules):
+        let permutation = [3, 0, 4, 1, 2];
+        let mut permuted = world.clone();
+        permuted.data.iter_mut().for_each(|v| *v = permutation[*v as usize]);
+        for rule in permuted.rule_phases[0].iter_mut() {
+            rule.symbols_needed.iter_mut().for_each(|v| *v = permutation[*v as usize]);
+            rule.output_symbol = permutation[rule.output_symbol as usize];
+        }
+
+        assert_eq!(world.behavior_fingerprint(50), permuted.behavior_fingerprint(50));
+        // whereas different rules (on this grid) behave differently:
+        let mut other = World::new(32, 5, 2.0, 2);
+        other.data = world.data.clone();
+        assert_ne!(world.behavior_fingerprint(50), other.behavior_fingerprint(50));
+    }
+
+    #[test]
+    fn test_randomize_from_seed_and_classify_run() {
+        let mut a = World::new(16, 5, 2.0, 1);
+        let mut b = World::new(16, 5, 2.0, 1);
+        a.randomize_from_seed(7);
+        b.randomize_from_seed(7);
+        assert_eq!(a.data, b.data);
+        b.randomize_from_seed(8);
+        assert_ne!(a.data, b.data);
+
+        let params = NoveltySearchParams { sample_frame_count: 50, min_end_cell_diff: 25, change_window: 5, change_window_gap: 0, burn_in_frames: 0 };
+        let summary = |active_frames, unique_frame_count, cell_change_diff_count| RunSummary {
+            seed: 1,
+            sample_frame_count: 50,
+            unique_frame_count,
+            cell_change_diff_count,
+            active_frames,
+            wavefront: None,
+            rule_count: 10,
+            avg_rule_length: 2.0,
+            #[cfg(feature="stats")]
+            step_latency: LatencyHistogram::new(),
+        };
+        assert_eq!(classify_run(&summary(10, 10, 0), &params), "static");
+        assert_eq!(classify_run(&summary(100, 30, 100), &params), "repetitive");
+        assert_eq!(classify_run(&summary(100, 50, 10), &params), "repetitive");
+        assert_eq!(classify_run(&summary(100, 50, 100), &params), "novel");
+        let wave = RunSummary { wavefront: Some(Wavefront { velocity: (1.0, 0.0), speed: 1.0, coherence: 1.0 }), ..summary(100, 50, 100) };
+        assert_eq!(classify_run(&wave, &params), "wave");
+    }
+
+    #[test]
+    fn test_palette_round_trip() {
+        let world = World::new(8, 5, 2.0, 1);
+        let path = std::env::temp_dir().join("color_rules_test_palette.pal");
+        write_palette(&path, &world.symbol_to_color).unwrap();
+        let colors = read_palette(&path).unwrap();
+        assert_eq!(colors, world.symbol_to_color);
+        let mut other = World::new(8, 5, 2.0, 2);
+        other.set_palette(colors);
+        assert_eq!(other.symbol_to_color, world.symbol_to_color);
+
+        std::fs::write(&path, "#ff0000\n00ff00\nnope\n").unwrap();
+        assert!(read_palette(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_change_events_reconstruct_step() {
+        let mut world = World::new(32, 5, 2.0, 1);
+        world.randomize();
+        world.step();
+        assert!(world.change_events().is_empty());
+        world.track_change_events();
+        for _ in 0..5 {
+            let mut reconstructed = world.data.clone();
+            world.step();
+            assert_eq!(world.change_events().len(), world.cell_changed_flags.iter().filter(|v| **v).count());
+            for event in world.change_events() {
+                let i = (event.y * world.size + event.x) as usize;
+                assert_eq!(reconstructed[i], event.old_symbol);
+                assert_eq!(world.rule_phases[0][event.rule_index.unwrap()].output_symbol, event.new_symbol);
+                reconstructed[i] = event.new_symbol;
+            }
+            assert_eq!(reconstructed, world.data);
+        }
+    }
+
+    #[test]
+    fn test_burn_in_frames_are_ignored() {
+        let run = |burn_in_frames| {
+            let params = NoveltySearchParams { sample_frame_count: 10, min_end_cell_diff: 0, change_window: 5, change_window_gap: 0, burn_in_frames };
+            let mut world = World::new(8, 5, 2.0, 1);
+            let mut novelty = NoveltyTracker::new(&params, world.data.len());
+            for count in 0..params.sample_end_frame() {
+                // a "startup" where the same frame keeps repeating (with a bit of flicker), and then every frame is different:
+                world.data[0] = count.saturating_sub(10) as u32;
+                world.cell_changed_flags.iter_mut().enumerate().for_each(|(i, v)| *v = (1..=5).contains(&count) && i == 1);
+                novelty.record_frame(count, &world);
+            }
+            (novelty.unique_frame_count(), novelty.cell_change_diff_count())
+        };
+        assert_eq!(run(0), (1, 1));
+        assert_eq!(run(10), (10, 0));
+    }
+
+    #[test]
+    fn test_hash_region() {
+        let mut world = World::new(16, 3, 1.0, 1);
+        // a blinker in the top-left corner of an otherwise dead world:
+        world.data.iter_mut().for_each(|v| *v = 0);
+        let blinker = Rect { x: 0, y: 0, width: 4, height: 4 };
+        let still = Rect { x: 8, y: 8, width: 8, height: 8 };
+        let still_hash = world.hash_region(still);
+        let mut blinker_hashes = std::collections::HashSet::new();
+        for frame in 0..4 {
+            world.data[16 + 1] = frame % 2;
+            blinker_hashes.insert(world.hash_region(blinker));
+            assert_eq!(world.hash_region(still), still_hash);
+        }
+        assert_eq!(blinker_hashes.len(), 2);
+        // same contents in a different place hash the same:
+        assert_eq!(world.hash_region(Rect { x: 8, y: 0, width: 8, height: 8 }), still_hash);
+        // but not in a different shape:
+        assert_ne!(world.hash_region(Rect { x: 0, y: 8, width: 16, height: 4 }), still_hash);
+        // and the hash doesn't depend on the build (fnv-1a of the little-endian width, height and cells):
+        let mut hasher = StableHasher::new();
+        [8, 8].iter().chain([0; 64].iter()).for_each(|v| hasher.write_u32(*v));
+        assert_eq!(still_hash, hasher.finish());
+    }
+
+    #[cfg(feature="parallel")]
+    #[test]
+    fn test_sequential_step_matches_parallel() {
+        let mut parallel = World::new(64, 5, 2.0, 1);
+        parallel.randomize();
+        parallel.track_last_changed();
+        let mut sequential = parallel.clone();
+        for _ in 0..30 {
+            parallel.step_within(None, true);
+            sequential.step_within(None, false);
+            assert_eq!(calculate_vec_hash(&parallel.data), calculate_vec_hash(&sequential.data));
+            assert_eq!(parallel.cell_changed_flags, sequential.cell_changed_flags);
+        }
+        assert_eq!(parallel.last_changed, sequential.last_changed);
+    }
+
+    #[test]
+    fn test_frozen_symbol_is_permanent() {
+        let run = |freeze:bool| {
+            let mut world = World::new(16, 3, 1.0, 1);
+            world.rule_phases = vec![vec![
+                WorldRule { symbols_needed: vec![1], output_symbol: 2, requires_motion: false, min_count: 1 },
+                WorldRule { symbols_needed: vec![0], output_symbol: 1, requires_motion: false, min_count: 1 },
+                WorldRule { symbols_needed: vec![2], output_symbol: 0, requires_motion: false, min_count: 1 },
+            ]];
+            if freeze {
+                world.frozen_symbols.insert(1);
+            }
+            world.data.iter_mut().for_each(|v| *v = 0);
+            world.data[0] = 1; // this one starts out as a 1, so it isn't frozen
+            let mut history = vec![];
+            for _ in 0..10 {
+                world.step();
+                history.push((world.data[0], world.data[8*16 + 8]));
+            }
+            history
+        };
+        let frozen = run(true);
+        // everything far from the spark becomes a 1 on the first step, and then stays that way:
+        assert!(frozen.iter().all(|(_, far)| *far == 1));
+        assert_ne!(frozen[0].0, 1);
+        // whereas without freezing it keeps cycling:
+        assert!(run(false).iter().any(|(_, far)| *far != 1));
+    }
+
+    #[test]
+    fn test_downsample() {
+        let mut world = World::new(8, 5, 2.0, 1);
+        world.randomize();
+        // a uniform 4x4 block in the bottom-right, and a block that 2 narrowly wins in the top-left:
+        for y in 4..8 {
+            for x in 4..8 {
+                world.data[y*8 + x] = 3;
+            }
+        }
+        for y in 0..4 {
+            for x in 0..4 {
+                world.data[y*8 + x] = if x == 0 && y < 3 { 4 } else { (x + y) as u32 % 2 * 2 };
+            }
+        }
+        let small = world.downsample(4);
+        assert_eq!(small.len(), 4);
+        assert_eq!(small[3], 3);
+        assert_eq!(small[0], 2);
+        assert_eq!(world.downsample(1), world.data);
+    }
+
+    #[test]
+    fn test_custom_transition_fn() {
+        // the built-in rules, re-implemented through the trait, give the same results:
+        let mut world = World::new(32, 5, 2.0, 1);
+        world.randomize();
+        let mut custom = world.clone();
+        custom.set_transition_fn(RuleTransition { rules: world.rule_phases[0].clone() });
+        for _ in 0..10 {
+            world.step();
+            custom.step();
+            assert_eq!(world.data, custom.data);
+        }
+
+        // and a neighborhood-sum rule that can't be expressed as color rules (a cell becomes 1 if exactly 3 cells of
+        // its neighborhood are 1, otherwise 0), which turns a row of three 1s into a column:
+        struct Three;
+        impl TransitionFn for Three {
+            fn transition(&self, neighborhood:&[u32; 9], _center:u32) -> u32 {
+                (neighborhood.iter().sum::<u32>() == 3) as u32
+            }
+        }
+        let mut world = World::new(8, 2, 1.0, 1);
+        world.data.iter_mut().for_each(|v| *v = 0);
+        world.data[4*8 + 3..4*8 + 6].iter_mut().for_each(|v| *v = 1);
+        world.set_transition_fn(Three);
+        world.step();
+        assert_eq!(world.to_ascii(), "00000000\n00000000\n00000000\n00001000\n00001000\n00001000\n00000000\n00000000\n");
+    }
+
+    #[test]
+    fn test_is_static() {
+        let mut world = World::new(8, 3, 1.0, 1);
+        world.rule_phases = vec![vec![WorldRule { symbols_needed: vec![0], output_symbol: 1, requires_motion: false, min_count: 1 }]];
+        world.data.iter_mut().for_each(|v| *v = 0);
+        world.step();
+        assert!(!world.is_static());
+        // everything is a 1 now, which no rule matches:
+        world.step();
+        assert!(world.is_static());
+    }
+
+    #[test]
+    fn test_grid_round_trip() {
+        let mut world = World::new(16, 5, 2.0, 1);
+        world.randomize();
+        world.data[0] = 4;
+        let path = std::env::temp_dir().join("color_rules_test_grid.grid");
+        write_grid(&path, world.size, &world.data).unwrap();
+        let mut restored = World::new(16, 5, 2.0, 1);
+        restored.load_grid(&path).unwrap();
+        assert_eq!(restored.data, world.data);
+
+        // the wrong size, or symbols the world doesn't have, are rejected:
+        assert!(World::new(8, 5, 2.0, 1).load_grid(&path).is_err());
+        assert!(World::new(16, 3, 1.0, 1).load_grid(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_transition_matrix() {
+        let mut world = World::new(8, 3, 1.0, 1);
+        world.rule_phases = vec![vec![WorldRule { symbols_needed: vec![0], output_symbol: 1, requires_motion: false, min_count: 1 }]];
+        world.data.iter_mut().for_each(|v| *v = 0);
+        let mut matrix = TransitionMatrix::new(3);
+        for _ in 0..3 {
+            world.step();
+            matrix.record(&world);
+        }
+        assert_eq!(matrix.count(0, 1), 64);
+        assert_eq!(matrix.counts.iter().sum::<u64>(), 64);
+        assert_eq!(matrix.probabilities(), vec![vec![0.0, 1.0, 0.0], vec![0.0; 3], vec![0.0; 3]]);
+    }
+
+    #[test]
+    fn test_canonical_hash() {
+        let mut world = World::new(16, 3, 1.0, 1);
+        world.data.iter_mut().for_each(|v| *v = 0);
+        // a small asymmetric blob that straddles the right edge:
+        for (x, y, v) in [(15, 3, 1), (0, 3, 2), (0, 4, 1), (1, 4, 1), (15, 5, 2)].iter() {
+            world.data[y*16 + x] = *v;
+        }
+        let mut shifted = world.clone();
+        for y in 0..16 {
+            for x in 0..16 {
+                shifted.data[((y + 7) % 16)*16 + (x + 5) % 16] = world.data[y*16 + x];
+            }
+        }
+        assert_ne!(calculate_vec_hash(&shifted.data), calculate_vec_hash(&world.data));
+        assert_eq!(shifted.canonical_hash(), world.canonical_hash());
+        // a different shape doesn't:
+        shifted.data[0] = 2;
+        assert_ne!(shifted.canonical_hash(), world.canonical_hash());
+        // and the hash doesn't depend on the build (fnv-1a of the little-endian cells, here of a blank grid that isn't
+        // shifted at all):
+        let mut blank = World::new(4, 2, 1.0, 1);
+        blank.data = vec![0; 16];
+        let mut hasher = StableHasher::new();
+        blank.data.iter().for_each(|v| hasher.write_u32(*v));
+        assert_eq!(blank.canonical_hash(), hasher.finish());
+    }
+
+    #[test]
+    fn test_gif_recorder_appends() {
+        let path = std::env::temp_dir().join("color_rules_test_append.gif");
+        let mut world = World::new(16, 3, 1.0, 1);
+        world.randomize();
+        let mut recorder = GifRecorder::create(&path, world.size, &world.symbol_to_color, Some(1)).unwrap();
+        for _ in 0..3 {
+            world.step();
+            recorder.add_frame(&world.data).unwrap();
+        }
+        // ...paused here, then continued:
+        for _ in 0..2 {
+            world.step();
+            recorder.add_frame(&world.data).unwrap();
+        }
+        assert_eq!(recorder.frame_count(), 5);
+        drop(recorder);
+
+        let mut reader = gif::Decoder::new(std::fs::File::open(&path).unwrap()).read_info().unwrap();
+        let mut decoded_frame_count = 0;
+        while reader.read_next_frame().unwrap().is_some() {
+            decoded_frame_count += 1;
+        }
+        assert_eq!(decoded_frame_count, 5);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_set_rejects_out_of_range() {
+        let mut world = World::new(8, 3, 1.0, 1);
+        world.set((2, 1), 2).unwrap();
+        assert_eq!(world.data[8 + 2], 2);
+        let before = world.data.clone();
+        match world.set((2, 1), 3) {
+            Err(WorldError::Cell(message)) => assert!(message.contains("symbol 3"), "{}", message),
+            other => panic!("expected a cell error, got {:?}", other),
+        }
+        assert!(world.set((8, 0), 0).is_err());
+        assert_eq!(world.data, before);
+    }
+
+    #[test]
+    fn test_set_wakes_up_neighborhood() {
+        // anything next to a 1 becomes a 2, so an all-0 world is static:
+        let mut world = World::new(8, 3, 1.0, 1);
+        world.set_rules(vec![WorldRule { symbols_needed: vec![1], output_symbol: 2, requires_motion: false, min_count: 1 }]).unwrap();
+        world.data = vec![0; 64];
+        world.step();
+        world.step();
+        assert!(world.is_static());
+
+        world.set((3, 3), 1).unwrap();
+        world.step();
+        for y in 0..8 {
+            for x in 0..8 {
+                let next_to = (2..5).contains(&x) && (2..5).contains(&y);
+                assert_eq!(world.data[y*8 + x], if next_to { 2 } else { 0 }, "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_early_decision_keeps_accepted_seeds() {
+        let config = WorldConfig { size: 32, symbol_count: 5, avg_symbols_per_rule: 2.0, transition_mode: TransitionMode::Rules, repulsion: false, edge_matching: false };
+        let params = NoveltySearchParams { sample_frame_count: 100, min_end_cell_diff: 5, change_window: 5, change_window_gap: 0, burn_in_frames: 0 };
+        let policy = EarlyDecisionPolicy { checkpoints: vec![0.25, 0.5], min_changed_fraction: 0.01 };
+        let (mut accepted_count, mut full_frames, mut early_frames) = (0, 0, 0);
+        for seed in 0..40 {
+            let mut world = World::from_config(&config, seed);
+            world.randomize_from_seed(seed);
+            let full = run_headless(&mut world, seed, &params, params.sample_end_frame());
+            let mut world = World::from_config(&config, seed);
+            world.randomize_from_seed(seed);
+            let early = run_headless_with_policy(&mut world, seed, &params, params.sample_end_frame(), Some(&policy));
+            let accepted = NoveltyFitness.score(&full) > params.min_end_cell_diff as f64;
+            if accepted {
+                accepted_count += 1;
+                assert_eq!(early.active_frames, full.active_frames, "seed {} was accepted but aborted early", seed);
+                assert_eq!(NoveltyFitness.score(&early), NoveltyFitness.score(&full));
+            }
+            full_frames += full.active_frames;
+            early_frames += early.active_frames;
+        }
+        assert!(accepted_count > 0);
+        assert!(early_frames < full_frames);
+    }
+
+    #[test]
+    fn test_changed_bounds_and_neighbors_changed() {
+        let mut world = World::new(16, 3, 1.0, 1);
+        world.rule_phases = vec![vec![WorldRule { symbols_needed: vec![1], output_symbol: 1, requires_motion: false, min_count: 1 }]];
+        world.data.iter_mut().for_each(|v| *v = 0);
+        world.data[6*16 + 5] = 1;
+        world.step();
+        // the 1 spreads to the 8 cells around it:
+        let bounds = world.changed_bounds().unwrap();
+        assert_eq!((bounds.x, bounds.y, bounds.width, bounds.height), (4, 5, 3, 3));
+        assert!(world.neighbors_changed(3, 4) && world.neighbors_changed(7, 8) && world.neighbors_changed(5, 6));
+        assert!(!world.neighbors_changed(2, 6) && !world.neighbors_changed(5, 9) && !world.neighbors_changed(12, 12));
+
+        world.data.iter_mut().for_each(|v| *v = 1);
+        world.step();
+        assert!(world.changed_bounds().is_none());
+        assert!(!world.neighbors_changed(5, 6));
+    }
+
+    #[test]
+    fn test_edge_matching_rules() {
+        let config = WorldConfig { size: 16, symbol_count: 8, avg_symbols_per_rule: 2.0, transition_mode: TransitionMode::Rules, repulsion: false, edge_matching: false };
+        let edge_matching_config = WorldConfig { edge_matching: true, ..config.clone() };
+        let (mut default_compatible, mut edge_matching_compatible, mut rule_count) = (0, 0, 0);
+        for seed in 0..20 {
+            let default_world = World::from_config(&config, seed);
+            let edge_matching_world = World::from_config(&edge_matching_config, seed);
+            // the default generation is untouched:
+            assert_eq!(default_world.rule_phases, World::new(16, 8, 2.0, seed).rule_phases);
+            assert_eq!(edge_matching_world.symbol_to_color, default_world.symbol_to_color);
+
+            let edge_colors = wang_edge_colors(8, seed);
+            let compatible_count = |world:&World| world.rule_phases[0].iter().filter(|rule| {
+                rule.symbols_needed.iter().all(|s| wang_compatible(&edge_colors[rule.output_symbol as usize], &edge_colors[*s as usize]))
+            }).count();
+            for (a, b) in default_world.rule_phases[0].iter().zip(edge_matching_world.rule_phases[0].iter()) {
+                assert_eq!(a.symbols_needed, b.symbols_needed);
+            }
+            default_compatible += compatible_count(&default_world);
+            edge_matching_compatible += compatible_count(&edge_matching_world);
+            rule_count += default_world.rule_phases[0].len();
+        }
+        assert!(edge_matching_compatible as f32 > default_compatible as f32 * 1.2, "{} vs {} of {} rules", edge_matching_compatible, default_compatible, rule_count);
+    }
+
+    #[test]
+    fn test_set_rules_round_trip() {
+        let mut world = World::new(16, 4, 2.0, 1);
+        world.apply_rule_mask(&vec![true; world.rules().len()]);
+        let rules = vec![
+            WorldRule { symbols_needed: vec![0, 3], output_symbol: 2, requires_motion: false, min_count: 1 },
+            WorldRule { symbols_needed: vec![1], output_symbol: 0, requires_motion: true, min_count: 4 },
+        ];
+        world.set_rules(rules.clone()).unwrap();
+        assert_eq!(world.rules(), &rules[..]);
+        assert!(world.rule_mask.is_none());
+
+        let invalid = vec![WorldRule { symbols_needed: vec![4], output_symbol: 0, requires_motion: false, min_count: 1 }];
+        match world.set_rules(invalid) {
+            Err(WorldError::Rule(message)) => assert!(message.contains("symbol 4"), "{}", message),
+            other => panic!("expected a rule error, got {:?}", other),
+        }
+        assert!(world.set_rules(vec![WorldRule { min_count: 10, ..rules[0].clone() }]).is_err());
+        assert!(world.set_rules(vec![]).is_err());
+        assert_eq!(world.rules(), &rules[..]);
+
+        // every phase would need new rules at once, so a multi-phase world is left alone:
+        let mut phased = World::new_with_phases(16, 4, 2.0, 1, 2);
+        let before = phased.rule_phases.clone();
+        match phased.set_rules(rules.clone()) {
+            Err(WorldError::Rule(message)) => assert!(message.contains("2 rule phases"), "{}", message),
+            other => panic!("expected a rule error, got {:?}", other),
+        }
+        assert_eq!(phased.rule_phases, before);
+        phased.apply_rule_mask(&vec![true; phased.rules().len()]);
+    }
+
+    #[test]
+    fn test_local_variety_map() {
+        let mut world = World::new(16, 2, 1.0, 1);
+        // a checkerboard on the left half, and solid on the right:
+        for y in 0..16 {
+            for x in 0..16 {
+                world.data[y*16 + x] = if x < 8 { ((x + y) % 2) as u32 } else { 0 };
+            }
+        }
+        let variety = world.local_variety_map();
+        assert!(variety[3*16 + 3] > 0.9, "{}", variety[3*16 + 3]);
+        assert_eq!(variety[8*16 + 12], 0.0);
+
+        let path = std::env::temp_dir().join("color_rules_test_variety.png");
+        world.save_local_variety_map(&path).unwrap();
+        let img = image::open(&path).unwrap().to_luma();
+        assert_eq!(img.dimensions(), (16, 16));
+        assert!(img.get_pixel(3, 3)[0] > 230);
+        assert_eq!(img.get_pixel(12, 8)[0], 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_draw_downscales_oversized_world() {
+        let mut world = World::new(32, 3, 1.0, 1);
+        world.data.iter_mut().for_each(|v| *v = 2);
+        let (r, g, b) = world.symbol_to_color[2];
+        let color = (u32::from(r) << 16) | (u32::from(g) << 8) | u32::from(b);
+        // 32 cells into 10 pixels isn't a whole ratio, and the buffer isn't square:
+        let untouched = 0x123456;
+        let mut buffer = vec![untouched; 12 * 10];
+        world.draw_to_buffer(&mut buffer, 12, 10);
+        for y in 0..10 {
+            for x in 0..12 {
+                assert_eq!(buffer[y*12 + x], if x < 10 { color } else { untouched }, "({}, {})", x, y);
+            }
+        }
+
+        // alternating columns average to the mix of the two colors:
+        for (i, v) in world.data.iter_mut().enumerate() {
+            *v = if i % 2 == 0 { 0 } else { 1 };
+        }
+        world.symbol_to_color = vec![(0, 0, 0), (200, 100, 50), (0, 0, 0)];
+        let mut buffer = vec![0; 16 * 16];
+        world.draw_to_buffer(&mut buffer, 16, 16);
+        assert!(buffer.iter().all(|c| *c == (100 << 16) | (50 << 8) | 25));
+
+        world.antialias = false;
+        world.draw_to_buffer(&mut buffer, 16, 16);
+        assert_eq!(buffer[0], 0);
+        assert_eq!(buffer[1], (200 << 16) | (100 << 8) | 50);
+    }
+
+    #[test]
+    fn test_compare_worlds() {
+        use std::cmp::Ordering;
+        let config = WorldConfig { size: 32, symbol_count: 5, avg_symbols_per_rule: 2.0, transition_mode: TransitionMode::Rules, repulsion: false, edge_matching: false };
+        let params = NoveltySearchParams { sample_frame_count: 100, min_end_cell_diff: 5, change_window: 5, change_window_gap: 0, burn_in_frames: 0 };
+        // seed 6 keeps going for the whole sample, while seed 8 goes static after a couple of frames:
+        let (lively, dead) = ((&config, 6), (&config, 8));
+        assert_eq!(compare_worlds(lively, dead, &NoveltyFitness, &params), Ordering::Greater);
+        assert_eq!(compare_worlds(dead, lively, &NoveltyFitness, &params), Ordering::Less);
+        assert_eq!(compare_worlds(lively, lively, &NoveltyFitness, &params), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_png_frame_filenames_sort() {
+        let digits = png_frame_digits(10000);
+        let filenames: Vec<String> = (0..10000).map(|i| png_frame_filename(i, digits)).collect();
+        let mut sorted = filenames.clone();
+        sorted.sort();
+        assert_eq!(sorted, filenames);
+        assert_eq!(filenames[0], "frame_00000.png");
+        assert_eq!(png_frame_filename(123455, png_frame_digits(123456)), "frame_123455.png");
+        assert_eq!(png_frame_filename(7, png_frame_digits(123456)), "frame_000007.png");
+
+        let dir = std::env::temp_dir().join("color_rules_test_png_frames");
+        let mut writer = PngFrameWriter::new(&dir, 2, 3).unwrap();
+        let mut world = World::new(16, 3, 1.0, 1);
+        world.randomize();
+        for _ in 0..3 {
+            world.step();
+            writer.write_frame(&world).unwrap();
+        }
+        assert_eq!(writer.frame_count(), 3);
+        assert_eq!(image::open(dir.join("frame_00002.png")).unwrap().to_rgb().dimensions(), (32, 32));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_wavefront() {
+        let params = NoveltySearchParams { sample_frame_count: 30, min_end_cell_diff: 5, change_window: 5, change_window_gap: 0, burn_in_frames: 0 };
+        let mut world = World::new(32, 3, 1.0, 1);
+        // a front spanning the whole height, moving one cell to the left per step (and wrapping around):
+        let mut novelty = NoveltyTracker::new(&params, world.data.len());
+        for frame in 0..30 {
+            let x = (40 - frame) % 32;
+            world.cell_changed_flags = (0..32*32).map(|i| i % 32 == x).collect();
+            novelty.record_frame(frame, &world);
+        }
+        let wavefront = novelty.wavefront().unwrap();
+        assert!((wavefront.velocity.0 + 1.0).abs() < 1e-6 && wavefront.velocity.1.abs() < 1e-6, "{:?}", wavefront);
+        assert!(wavefront.coherence > 0.99);
+
+        // changes scattered all over the place aren't a front:
+        use rand::{SeedableRng, rngs::StdRng};
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut novelty = NoveltyTracker::new(&params, world.data.len());
+        for frame in 0..30 {
+            world.cell_changed_flags = (0..32*32).map(|_| rng.gen::<f32>() < 0.01).collect();
+            novelty.record_frame(frame, &world);
+        }
+        assert_eq!(novelty.wavefront(), None);
+    }
+
+    #[test]
+    fn test_harmonize_palette() {
+        let mut world = World::new(8, 4, 1.0, 1);
+        world.symbol_to_color = vec![(255, 0, 0), (0, 0, 255), (0, 255, 0), (255, 255, 0)]; // hues: 0, 240, 120, 60
+        // 0 and 2 interconvert a lot, 2 and 1 less so, and 3 hardly at all:
+        let mut matrix = TransitionMatrix::new(4);
+        let index = |old_symbol:usize, new_symbol:usize| old_symbol*4 + new_symbol;
+        matrix.counts[index(0, 2)] = 50;
+        matrix.counts[index(2, 0)] = 40;
+        matrix.counts[index(2, 1)] = 20;
+        matrix.counts[index(1, 3)] = 1;
+        assert_eq!(matrix.affinity_chain(), vec![2, 0, 1, 3]);
+
+        let old_colors = world.symbol_to_color.clone();
+        let permutation = world.harmonize_palette(&matrix);
+        let mut sorted = permutation.clone();
+        sorted.sort();
+        assert_eq!(sorted, vec![0, 1, 2, 3]);
+        for (symbol, color_index) in permutation.iter().enumerate() {
+            assert_eq!(world.symbol_to_color[symbol], old_colors[*color_index]);
+        }
+        // the chain gets the colors in hue order (red, yellow, green, blue):
+        assert_eq!(world.symbol_to_color, vec![(255, 255, 0), (0, 255, 0), (255, 0, 0), (0, 0, 255)]);
+    }
+
+    #[test]
+    fn test_mutate_seed() {
+        let mutation = mutate_seed(906339142304154875, 0, 3);
+        assert_eq!(mutation, mutate_seed(906339142304154875, 0, 3));
+        assert_eq!(mutation.flipped_bits.len(), 3);
+        assert_eq!((mutation.parent ^ mutation.child).count_ones(), 3);
+        assert!(mutation.flipped_bits.iter().all(|b| (mutation.parent ^ mutation.child) & (1 << b) != 0));
+        assert_ne!(mutation.child, mutate_seed(906339142304154875, 1, 3).child);
+        assert_eq!(mutate_seed(5, 7, 64).child, !5);
+    }
+
+    #[test]
+    fn test_draw_rgb565_and_indexed() {
+        assert_eq!(rgb565(255, 0, 0), 0xF800);
+        assert_eq!(rgb565(0, 255, 0), 0x07E0);
+        assert_eq!(rgb565(0, 0, 255), 0x001F);
+        assert_eq!(rgb565(0x12, 0x34, 0x56), (0x12 >> 3) << 11 | (0x34 >> 2) << 5 | 0x56 >> 3);
+
+        let mut world = World::new(4, 3, 1.0, 1);
+        world.symbol_to_color = vec![(0, 0, 0), (255, 255, 255), (0x12, 0x34, 0x56)];
+        world.data = vec![0, 1, 2, 1, 2, 2, 0, 0, 1, 1, 1, 1, 2, 0, 2, 0];
+        let mut buffer = vec![0u16; 16];
+        world.draw_to_buffer_rgb565(&mut buffer);
+        assert_eq!(&buffer[..3], &[0x0000, 0xFFFF, 0x11AA]);
+
+        let mut indexed = vec![0u8; 16];
+        world.draw_indexed(&mut indexed);
+        assert!(indexed.iter().zip(world.data.iter()).all(|(i, v)| u32::from(*i) == *v));
+    }
+
+    #[test]
+    fn test_quiescent_symbol_does_not_change_results() {
+        let rules = vec![
+            WorldRule { symbols_needed: vec![0, 1], output_symbol: 2, requires_motion: false, min_count: 1 },
+            WorldRule { symbols_needed: vec![2], output_symbol: 3, requires_motion: false, min_count: 2 },
+            WorldRule { symbols_needed: vec![3], output_symbol: 0, requires_motion: false, min_count: 1 },
+            WorldRule { symbols_needed: vec![0, 3], output_symbol: 1, requires_motion: true, min_count: 1 },
+        ];
+        let mut plain = World::new(32, 4, 1.0, 1);
+        plain.set_rules(rules).unwrap();
+        plain.data = (0..32*32).map(|i: u32| if i.wrapping_mul(2654435761) % 37 == 0 { 1 + i % 3 } else { 0 }).collect();
+        let mut skipping = plain.clone();
+        skipping.quiescent_symbol = Some(0);
+        for frame in 0..40 {
+            plain.step();
+            skipping.step();
+            assert_eq!(plain.data, skipping.data, "frame {}", frame);
+        }
+
+        // a rule that fires on an all-quiescent neighborhood still has to be applied:
+        let mut world = World::new(8, 2, 1.0, 1);
+        world.set_rules(vec![WorldRule { symbols_needed: vec![0], output_symbol: 1, requires_motion: false, min_count: 9 }]).unwrap();
+        world.data = vec![0; 64];
+        world.quiescent_symbol = Some(0);
+        world.step();
+        assert!(world.data.iter().all(|v| *v == 1));
+    }
+
+    #[test]
+    fn test_checksum() {
+        let world = World::new(16, 4, 2.0, 1);
+        assert_eq!(world.checksum(), world.clone().checksum());
+        assert_eq!(world.checksum(), World::new(16, 4, 2.0, 1).checksum());
+
+        type Change = Box<dyn Fn(&mut World)>;
+        let changes: Vec<Change> = vec![
+            Box::new(|w| w.data[5] = (w.data[5] + 1) % 4),
+            Box::new(|w| w.symbol_to_color[2].1 ^= 1),
+            Box::new(|w| { let mut rules = w.rules().to_vec(); rules[0].output_symbol = (rules[0].output_symbol + 1) % 4; w.set_rules(rules).unwrap(); }),
+            Box::new(|w| { let mut rules = w.rules().to_vec(); rules[0].requires_motion = !rules[0].requires_motion; w.set_rules(rules).unwrap(); }),
+            Box::new(|w| { let mut rules = w.rules().to_vec(); rules.pop(); w.set_rules(rules).unwrap(); }),
+            Box::new(|w| w.apply_rule_mask(&(0..w.rules().len()).map(|i| i != 0).collect::<Vec<_>>())),
+            Box::new(|w| w.transition_mode = TransitionMode::Majority),
+            Box::new(|w| { w.locked_symbols.insert(3); }),
+            Box::new(|w| { w.frozen_symbols.insert(3); }),
+            Box::new(|w| w.symbol_count = 5),
+            Box::new(|w| w.enable_intensity(vec![0; w.rules().len()]).unwrap()),
+            Box::new(|w| *w = World::new(32, 4, 2.0, 1)),
+        ];
+        for (i, change) in changes.iter().enumerate() {
+            let mut changed = world.clone();
+            change(&mut changed);
+            assert_ne!(changed.checksum(), world.checksum(), "change {}", i);
+        }
+        // with more than one rule phase, the frame decides which phase the next step uses (but only that):
+        let phased = World::new_with_phases(16, 4, 2.0, 1, 2);
+        let mut next_phase = phased.clone();
+        next_phase.frame += 1;
+        assert_ne!(next_phase.checksum(), phased.checksum());
+        next_phase.frame += 1;
+        assert_eq!(next_phase.checksum(), phased.checksum());
+
+        // the reference fnv-1a value, so that the hash can't drift:
+        let mut s = StableHasher::new();
+        s.write_bytes(b"a");
+        assert_eq!(s.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_replay_divergence() {
+        let config = WorldConfig { size: 32, symbol_count: 5, avg_symbols_per_rule: 2.0, transition_mode: TransitionMode::Rules, repulsion: false, edge_matching: false };
+        for seed in 0..5 {
+            assert_eq!(replay_divergence(&config, seed, 50), None, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_intensity() {
+        let mut world = World::new(4, 2, 1.0, 1);
+        world.set_rules(vec![
+            WorldRule { symbols_needed: vec![0, 1], output_symbol: 1, requires_motion: false, min_count: 1 },
+            WorldRule { symbols_needed: vec![1], output_symbol: 0, requires_motion: false, min_count: 9 },
+        ]).unwrap();
+        assert!(world.enable_intensity(vec![-100]).is_err());
+        world.enable_intensity(vec![-100, 50]).unwrap();
+        world.symbol_to_color = vec![(10, 20, 30), (200, 100, 50)];
+        world.data = vec![0; 16];
+        world.data[5] = 1;
+
+        // full intensity is the pure color:
+        let mut buffer = vec![0; 16];
+        world.draw_to_buffer(&mut buffer, 4, 4);
+        assert_eq!(buffer[5], (200 << 16) | (100 << 8) | 50);
+        assert_eq!(buffer[0], (10 << 16) | (20 << 8) | 30);
+
+        // the 8 neighbors of the 1 turn into 1s themselves (through the first rule), and dim:
+        world.step();
+        let intensity = world.intensity().unwrap();
+        assert_eq!(intensity[0], 155);
+        assert_eq!(intensity[5], 255);
+        assert_eq!(intensity[15], 255);
+        world.step(); // the middle is all 1s now, so the second rule turns it back to 0 (brightening, but capped)
+        assert_eq!(world.intensity().unwrap()[5], 255);
+        world.step();
+        world.step();
+        assert!(world.intensity().unwrap().iter().any(|v| *v < 155));
+
+        // and intensity 0 is black:
+        world.intensity.as_mut().unwrap()[0] = 0;
+        world.draw_to_buffer(&mut buffer, 4, 4);
+        assert_eq!(buffer[0], 0);
+    }
+
+    #[test]
+    fn test_apply_perturbation() {
+        let mut world = World::new(32, 5, 2.0, 1);
+        world.randomize_from_seed(1);
+        for _ in 0..20 {
+            world.step();
+        }
+        let before = world.clone();
+        let mut a = world.clone();
+        let mut b = world.clone();
+        let flipped = a.apply_perturbation(0.1, 7);
+        assert_eq!(flipped, b.apply_perturbation(0.1, 7));
+        assert_eq!(a.data, b.data);
+        assert_eq!(flipped.len(), 102);
+        for i in 0..a.data.len() {
+            assert_eq!(a.data[i] != before.data[i], flipped.contains(&i), "cell {}", i);
+        }
+        assert!(flipped.iter().all(|i| a.neighborhood_changed_flags[*i]));
+        assert_ne!(flipped, world.clone().apply_perturbation(0.1, 8));
+        assert!(world.apply_perturbation(0.0, 7).is_empty());
+    }
+
+    #[test]
+    fn test_rule_complexity_penalty() {
+        let params = NoveltySearchParams { sample_frame_count: 50, min_end_cell_diff: 5, change_window: 5, change_window_gap: 0, burn_in_frames: 0 };
+        let mut simple = World::new(32, 5, 2.0, 6);
+        simple.randomize_from_seed(6);
+        // the first matching rule wins, so copies of the rules tacked onto the end never fire and the behavior is the same:
+        let mut bloated = simple.clone();
+        let rules = bloated.rules().to_vec();
+        bloated.set_rules(rules.iter().chain(rules.iter()).cloned().collect()).unwrap();
+        assert_eq!(bloated.rule_complexity().0, 2 * simple.rule_complexity().0);
+        assert_eq!(bloated.rule_complexity().1, simple.rule_complexity().1);
+
+        let simple_summary = run_headless(&mut simple, 6, &params, params.sample_end_frame());
+        let bloated_summary = run_headless(&mut bloated, 6, &params, params.sample_end_frame());
+        assert_eq!(simple.data, bloated.data);
+        assert_eq!(NoveltyFitness.score(&simple_summary), NoveltyFitness.score(&bloated_summary));
+        let fitness = RuleComplexityPenalty { fitness: NoveltyFitness, per_rule: 0.5, per_needed_symbol: 0.1 };
+        assert!(fitness.score(&simple_summary) > fitness.score(&bloated_summary));
+    }
+
+    #[test]
+    fn test_autocorrelation() {
+        // vertical stripes 4 cells wide, alternating between two symbols (so the pattern repeats every 8 columns):
+        let mut world = World::new(32, 2, 1.0, 1);
+        world.data = (0..32*32).map(|i| (i % 32 / 4) % 2).collect();
+        let correlation = world.autocorrelation(16);
+        assert_eq!(correlation.len(), 17);
+        assert_eq!(correlation[0], 1.0);
+        // vertically it always matches, horizontally it matches for all but `lag` of every 4 columns (up to 4):
+        assert_eq!(correlation[2], 0.75);
+        assert_eq!(correlation[4], 0.5);
+        assert_eq!(correlation[8], 1.0);
+        assert_eq!(correlation[12], 0.5);
+        assert_eq!(correlation[16], 1.0);
+
+        // noise decorrelates straight away:
+        world.randomize_from_seed(1);
+        let correlation = world.autocorrelation(3);
+        assert!(correlation[1..].iter().all(|c| (c - 0.5).abs() < 0.05), "{:?}", correlation);
+    }
+
+    #[test]
+    fn test_parameter_sweep() {
+        assert!(check_generation_params(5, 2.0).is_ok());
+        assert!(check_generation_params(1, 0.5).is_err());
+        assert!(check_generation_params(5, 5.0).is_err());
+        assert!(check_generation_params(5, 0.0).is_err());
+        assert!(check_generation_params(64, 40.0).is_err()); // would need an astronomical number of rules
+
+        let config = WorldConfig { size: 16, ..WorldConfig::default() };
+        let params = NoveltySearchParams { sample_frame_count: 20, min_end_cell_diff: 5, change_window: 5, change_window_gap: 0, burn_in_frames: 0 };
+        let grid = [(3, 1.0), (5, 2.5)];
+        let cells = parameter_sweep(&config, &grid, &[1, 2, 3], &params, 40);
+        assert_eq!(cells.len(), 2);
+        assert_eq!((cells[1].symbol_count, cells[1].avg_symbols_per_rule, cells[1].seed_count), (5, 2.5, 3));
+        for cell in cells.iter() {
+            assert!(cell.interesting_fraction >= 0.0 && cell.interesting_fraction <= 1.0);
+            assert!(cell.mean_entropy >= 0.0 && cell.mean_entropy <= (cell.symbol_count as f64).log2() + 1e-9);
+            assert!(cell.mean_active_frames >= 1.0 && cell.mean_active_frames <= 40.0);
+            assert_eq!(cell.to_csv_row().split(',').count(), SweepCell::CSV_HEADER.split(',').count());
+        }
+        assert_eq!(cells, parameter_sweep(&config, &grid, &[1, 2, 3], &params, 40));
+    }
+
+    #[test]
+    fn test_rle_world_round_trip() {
+        let mut world = World::new(32, 5, 2.0, 3);
+        let mut rules = world.rules().to_vec();
+        rules.insert(0, WorldRule { symbols_needed: vec![1, 2], output_symbol: 4, requires_motion: true, min_count: 2 });
+        world.set_rules(rules).unwrap();
+        world.apply_rule_mask(&(0..world.rules().len()).map(|i| i != 3).collect::<Vec<_>>());
+        world.frozen_symbols.insert(4);
+        world.locked_symbols.insert(0);
+        world.randomize_from_seed(3);
+        for _ in 0..15 {
+            world.step();
+        }
+
+        let text = world.to_rle_text();
+        assert!(text.starts_with(&format!("rle-world {} size=32 symbols=5 ", RLE_WORLD_VERSION)), "{}", text.lines().next().unwrap());
+        let mut loaded = World::from_rle_text(&text).unwrap();
+        assert_eq!(loaded.to_rle_text(), text);
+        assert_eq!(loaded.checksum(), world.checksum());
+        assert_eq!(loaded.frame, world.frame);
+        assert_eq!(loaded.is_static(), world.is_static());
+        for frame in 0..30 {
+            world.step();
+            loaded.step();
+            assert_eq!(loaded.data, world.data, "frame {}", frame);
+        }
+
+        let path = std::env::temp_dir().join("rle_world_round_trip.rle");
+        world.save_rle(&path).unwrap();
+        assert_eq!(World::load_rle(&path).unwrap().checksum(), world.checksum());
+        std::fs::remove_file(&path).unwrap();
+
+        let future = text.replacen(&format!("rle-world {}", RLE_WORLD_VERSION), "rle-world 99", 1);
+        match World::from_rle_text(&future) {
+            Err(WorldError::Rle(message)) => assert!(message.contains("version 99"), "{}", message),
+            _ => panic!("expected an rle error"),
+        }
+        assert!(World::from_rle_text(&text.replace("grid 0*", "grid 9*")).is_err());
+        assert!(World::from_rle_text(&text.replace("size=32", "size=30")).is_err());
+        // nothing is allocated for a header or run that asks for too much:
+        let huge = [text.replace("size=32", "size=65536"), text.replace("size=32", "size=8192"), text.replace("symbols=5", "symbols=4000000000"), text.replace("grid 0*", "grid 0*4294967295 0*")];
+        for (i, huge) in huge.iter().enumerate() {
+            match World::from_rle_text(huge) {
+                Err(WorldError::Rle(_)) => {}
+                _ => panic!("expected an rle error for huge file {}", i),
+            }
+        }
+        let with_radius = |radius:&str| text.replacen(&format!("radius={}", world.neighborhood_radius), &format!("radius={}", radius), 1);
+        assert_eq!(World::from_rle_text(&with_radius("15")).unwrap().neighborhood_radius, 15);
+        for radius in ["0", "16", "4294967295"].iter() {
+            match World::from_rle_text(&with_radius(radius)) {
+                Err(WorldError::Rle(message)) => assert!(message.contains("radius"), "{}", message),
+                _ => panic!("expected an rle error for radius={}", radius),
+            }
+        }
+
+        // the rule mask is applied by index in every phase, so rules can only be `off` where the phases line up:
+        let header = format!("rle-world {} size=4 symbols=2 radius=1 mode=rules frame=0 palette=#000000,#ffffff\n", RLE_WORLD_VERSION);
+        let with_rules = |rules:&str| format!("{}{}grid 0*16\n", header, rules);
+        let mut lined_up = World::from_rle_text(&with_rules("phase\nrule 0 -> 1 off\nrule 1 -> 0\nphase\nrule 0 -> 1\nrule 1 -> 0\n")).unwrap();
+        assert_eq!(lined_up.rule_mask, Some(vec![false, true]));
+        lined_up.step();
+        lined_up.step();
+        assert_eq!(lined_up.checksum(), World::from_rle_text(&lined_up.to_rle_text()).unwrap().checksum());
+        for rules in ["phase\nrule 0 -> 1 off\nphase\nrule 0 -> 1\nrule 1 -> 0\n", "phase\nrule 0 -> 1\nphase\nrule 1 -> 0 off\n"].iter() {
+            match World::from_rle_text(&with_rules(rules)) {
+                Err(WorldError::Rle(message)) => assert!(message.contains("off"), "{}", message),
+                _ => panic!("expected an rle error for {:?}", rules),
+            }
+        }
+    }
+
+    #[test]
+    fn test_rotate_hues() {
+        let colors = vec![(255, 0, 0), (0, 255, 0), (128, 128, 128), (18, 52, 86), (200, 100, 50)];
+        assert_eq!(rotate_hues(&colors, 0.0), colors);
+        assert_eq!(rotate_hues(&colors, 360.0), colors);
+        assert_eq!(rotate_hues(&colors, 120.0)[..3], [(0, 255, 0), (0, 0, 255), (128, 128, 128)]);
+        assert_eq!(rotate_hues(&colors, -120.0)[0], (0, 0, 255));
+        // going round in steps comes back to (almost exactly) the same colors:
+        let mut rotated = colors.clone();
+        for _ in 0..3 {
+            rotated = rotate_hues(&rotated, 120.0);
+        }
+        for (a, b) in rotated.iter().zip(colors.iter()) {
+            assert!((i32::from(a.0) - i32::from(b.0)).abs() <= 1 && (i32::from(a.1) - i32::from(b.1)).abs() <= 1 && (i32::from(a.2) - i32::from(b.2)).abs() <= 1, "{:?} vs {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_change_highlight() {
+        let mut world = World::new(4, 2, 1.0, 1);
+        world.set_rules(vec![WorldRule { symbols_needed: vec![0, 1], output_symbol: 1, requires_motion: false, min_count: 1 }]).unwrap();
+        world.symbol_to_color = vec![(100, 50, 200), (200, 100, 50)];
+        world.data = vec![0; 16];
+        world.data[0] = 1;
+        world.step(); // the 8 neighbors of cell 0 change to 1
+        world.change_highlight = Some(0.5);
+        let mut buffer = vec![0; 16];
+        world.draw_to_buffer(&mut buffer, 4, 4);
+        assert_eq!(buffer[1], (200 << 16) | (100 << 8) | 50); // changed
+        assert_eq!(buffer[0], (100 << 16) | (50 << 8) | 25); // 1 before and after
+        assert_eq!(buffer[10], (50 << 16) | (25 << 8) | 100); // 0 before and after
+
+        world.change_highlight = None;
+        world.draw_to_buffer(&mut buffer, 4, 4);
+        assert_eq!(buffer[0], (200 << 16) | (100 << 8) | 50);
+    }
+
+    #[test]
+    fn test_spark() {
+        let config = WorldConfig { size: 128, ..WorldConfig::default() };
+        for density in [0.01, 0.05, 0.2].iter() {
+            let world = World::spark(&config, 1, *density, 2);
+            let fraction = world.data.iter().filter(|v| **v != 0).count() as f32 / world.data.len() as f32;
+            assert!((fraction - density).abs() < density * 0.2, "{} vs {}", fraction, density);
+            // the sparks use every other symbol:
+            assert!(world.histogram()[1..].iter().all(|count| *count > 0));
+        }
+        assert_eq!(World::spark(&config, 1, 0.05, 2).data, World::spark(&config, 1, 0.05, 2).data);
+        assert_ne!(World::spark(&config, 1, 0.05, 2).data, World::spark(&config, 1, 0.05, 3).data);
+        assert!(World::spark(&config, 1, 0.0, 2).data.iter().all(|v| *v == 0));
+    }
+
+    #[test]
+    fn test_view_offset() {
+        let size = 8;
+        let data: Vec<u32> = (0..size*size).map(|i| i % 5).collect();
+        assert_eq!(roll_grid(&data, size, (size as i32, size as i32)), data);
+        assert_eq!(roll_grid(&data, size, (0, 0)), data);
+        // (1, 0) moves every cell one column to the right, with the last column wrapping around to the first:
+        let rolled = roll_grid(&data, size, (1, 0));
+        for y in 0..size {
+            for x in 0..size {
+                assert_eq!(rolled[(y * size + (x + 1) % size) as usize], data[(y * size + x) as usize]);
+            }
+        }
+        assert_eq!(roll_grid(&rolled, size, (-1, 0)), data);
+
+        // drawing with `view_offset` matches rolling the grid, and leaves the grid itself alone:
+        let mut world = World::new(size, 5, 1.0, 0);
+        world.data = data.clone();
+        world.view_offset = (3, -2);
+        let mut indexed = vec![0; data.len()];
+        world.draw_indexed(&mut indexed);
+        assert!(indexed.iter().zip(roll_grid(&data, size, (3, -2))).all(|(a, b)| u32::from(*a) == b));
+        let mut buffer = vec![0; data.len()];
+        world.draw_to_buffer(&mut buffer, size as usize, size as usize);
+        let mut unrolled = world.clone();
+        unrolled.view_offset = (0, 0);
+        unrolled.data = roll_grid(&data, size, (3, -2));
+        let mut expected = vec![0; data.len()];
+        unrolled.draw_to_buffer(&mut expected, size as usize, size as usize);
+        assert_eq!(buffer, expected);
+        assert_eq!(world.data, data);
+    }
+
+    #[test]
+    fn test_marker_recorder() {
+        let path = std::env::temp_dir().join("color_rules_test_markers.gif");
+        let mut world = World::new(16, 3, 1.0, 1);
+        world.randomize();
+        let mut recorder = MarkerRecorder::new();
+        let step = |world:&mut World, recorder:&mut MarkerRecorder| {
+            world.step();
+            recorder.add_frame(&world.data).unwrap();
+        };
+        for _ in 0..4 {
+            step(&mut world, &mut recorder);
+        }
+        recorder.start(&path, world.size, &world.symbol_to_color, None, 1).unwrap();
+        let start_frame = world.frame;
+        for _ in 0..7 {
+            step(&mut world, &mut recorder);
+        }
+        // a second start marker doesn't restart the recording:
+        recorder.start(&path, world.size, &world.symbol_to_color, None, 1).unwrap();
+        assert!(recorder.is_recording());
+        let recorded = recorder.stop();
+        assert_eq!(recorded, Some((world.frame - start_frame) as usize));
+        assert_eq!(recorded, Some(7));
+        for _ in 0..3 {
+            step(&mut world, &mut recorder);
+        }
+        assert!(!recorder.is_recording());
+        assert_eq!(recorder.stop(), None);
+
+        let mut reader = gif::Decoder::new(std::fs::File::open(&path).unwrap()).read_info().unwrap();
+        let mut decoded_frame_count = 0;
+        while reader.read_next_frame().unwrap().is_some() {
+            decoded_frame_count += 1;
+        }
+        assert_eq!(decoded_frame_count, 7);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_neighborhood_histogram() {
+        let mut world = World::new(16, 3, 1.0, 1);
+        world.data = vec![2; 16*16];
+        assert_eq!(world.neighborhood_histogram().into_iter().collect::<Vec<_>>(), vec![(0b100, 16*16)]);
+
+        // all 0 apart from one 1, so every neighborhood is {0} except the 9 around the 1, which are {0, 1}:
+        world.data = vec![0; 16*16];
+        world.data[5*16 + 5] = 1;
+        let histogram = world.neighborhood_histogram();
+        assert_eq!(histogram.len(), 2);
+        assert_eq!(histogram[&0b01], 16*16 - 9);
+        assert_eq!(histogram[&0b11], 9);
+
+        let mut neighborhoods = NeighborhoodHistogram::new();
+        neighborhoods.record(&world);
+        world.data[5*16 + 5] = 0;
+        neighborhoods.record(&world);
+        assert_eq!(neighborhoods.distinct_count(), 2);
+        assert_eq!(neighborhoods.count(0b01), 2*16*16 - 9);
+        assert_eq!(neighborhoods.count(0b100), 0);
+        let top = neighborhoods.top(5);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].0, 0b01);
+        assert!((top[0].1 - (2.0*256.0 - 9.0) / 512.0).abs() < 1e-9);
+        assert!((top.iter().map(|(_, fraction)| fraction).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert_eq!(neighborhoods.top(1).len(), 1);
+    }
+
+    #[test]
+    fn test_upscaled_gif() {
+        let path = std::env::temp_dir().join("color_rules_test_upscaled.gif");
+        let data = vec![
+            0, 1, 2, 3,
+            3, 2, 1, 0,
+            0, 0, 1, 1,
+            2, 2, 3, 3,
+        ];
+        let colors = vec![(0, 0, 0), (255, 0, 0), (0, 255, 0), (0, 0, 255)];
+        let mut recorder = GifRecorder::create_upscaled(&path, 4, &colors, None, 2).unwrap();
+        recorder.add_frame(&data).unwrap();
+        drop(recorder);
+
+        let mut reader = gif::Decoder::new(std::fs::File::open(&path).unwrap()).read_info().unwrap();
+        assert_eq!((reader.width(), reader.height()), (8, 8));
+        let frame = reader.read_next_frame().unwrap().unwrap();
+        assert_eq!((frame.width, frame.height), (8, 8));
+        for y in 0..8 {
+            for x in 0..8 {
+                assert_eq!(u32::from(frame.buffer[y*8 + x]), data[(y/2)*4 + x/2], "pixel ({}, {})", x, y);
+            }
+        }
+        let palette = reader.global_palette().unwrap();
+        assert_eq!(&palette[3*3..3*3+3], &[0, 0, 255]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_explain_cell() {
+        let config = WorldConfig { size: 16, ..WorldConfig::default() };
+        let mut world = World::from_config(&config, 3);
+        world.randomize_from_seed(3);
+        world.track_change_events();
+        for _ in 0..10 {
+            let explanations: Vec<CellExplanation> = (0..16*16).map(|i| world.explain_cell((i % 16, i / 16))).collect();
+            world.step();
+            // every explanation matches what the step actually did:
+            for (explanation, symbol) in explanations.iter().zip(world.data.iter()) {
+                assert_eq!(explanation.new_symbol, *symbol);
+            }
+            for event in world.change_events() {
+                assert_eq!(explanations[(event.y*16 + event.x) as usize].rule_index, event.rule_index);
+            }
+        }
+
+        let mut world = World::new(4, 3, 1.0, 1);
+        world.data = vec![0; 16];
+        world.data[5] = 1;
+        world.set_rules(vec![WorldRule { symbols_needed: vec![0, 1], output_symbol: 2, requires_motion: false, min_count: 1 }]).unwrap();
+        let explanation = world.explain_cell((0, 0));
+        assert_eq!(explanation.neighborhood, vec![0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!((explanation.old_symbol, explanation.new_symbol, explanation.rule_index), (0, 2, Some(0)));
+        assert!(explanation.to_string().contains("0 -> 2 by rule 0 (needs 0 1)"));
+        assert!(explanation.to_json().contains("\"rule_index\":0,\"rule\":{\"symbols_needed\":[0,1],"));
+        let explanation = world.explain_cell((3, 3));
+        assert_eq!((explanation.old_symbol, explanation.new_symbol, explanation.rule_index, &explanation.rule), (0, 0, None, &None));
+        assert!(explanation.to_json().ends_with("\"rule_index\":null,\"rule\":null}"));
+    }
+}
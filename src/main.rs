@@ -1,288 +1,13 @@
-use std::mem;
 use std::time::*;
-use rand::thread_rng;
-use rand::Rng;
 use minifb::{WindowOptions, Window};
-use rayon::prelude::*;
-
-struct WorldRule {
-    symbols_needed: Vec<u32>,
-    output_symbol: u32,
-}
-
-struct World {
-    size: u32,
-    data: Vec<u32>,
-    prev_data: Vec<u32>,
-    cell_changed_flags: Vec<bool>,
-    neighborhood_changed_flags: Vec<bool>,
-    symbol_count: u32,
-    symbol_to_color: Vec<(u8, u8, u8)>,
-    rules: Vec<WorldRule>,
-}
-
-impl World {
-
-    pub fn new(world_size:u32, symbol_count:u32, avg_symbols_per_rule:u32, seed:u64) -> World {
-        assert!( (world_size as f32).log(2.0) % 1.0 == 0.0, "World size must be a power of 2.");
-
-        // includes end
-        use rand::{SeedableRng, rngs::StdRng};
-        let mut rng = StdRng::seed_from_u64(seed);
-        let mut random = |start:u32, end:u32| -> u32 {
-            let r: f32 = rng.gen::<f32>();
-            start + (r * (end-start) as f32).round() as u32
-        };
-
-        let mut symbol_to_color = Vec::new();
-        for _i in 0..symbol_count {
-            let r = random(0, 255) as u8;
-            let g = random(0, 255) as u8;
-            let b = random(0, 255) as u8;
-            symbol_to_color.push((r, g, b));
-        }
-
-        // (indent means equals)
-        //
-        // > probabiliy of at least one rule match at a position
-        //   > one
-        //   > minus
-        //   > the probability of none of the rules matching at a position
-        //     > the product of the probabilities of each rule not matching
-        //       > the probability of a random rule rule not matching (i.e. at least one symbol doesn't match)
-        //         > one
-        //         > minus
-        //         > the probability of all the symbols of the rule *matching*
-        //           > the probability of a particular symbol existing in a particular neighborhood
-        //             > the probability that a symbol is in at least one of the 9 cells
-        //               > one
-        //               > minus
-        //               > the probability that the symbol is in NONE of the squares
-        //                 > the probabiliy that a cell doesn't contain a particular symbol
-        //                   > 1 - (1 / number_of_symbols)
-        //                 > to the power of
-        //                 > nine
-        //           > to the power of
-        //           > the average number of symbols in a rule
-        //       > to the power of
-        //       > the number of rules
-        let mut rule_count = 1;
-        let mut prob_match = 0.0;
-        while prob_match < 0.999 {
-          rule_count *= 2;
-          let a = (1.0 - (1.0 / symbol_count as f32)).powf(9.0);
-          let b = 1.0 - (1.0 - a).powf(avg_symbols_per_rule as f32);
-          prob_match = 1.0 - b.powf(rule_count as f32);
-        }
-
-        // #[cfg(feature="interactive")] {
-        //     println!("world_size: {}", world_size);
-        //     println!("symbol_count: {}", symbol_count);
-        //     println!("avg_symbols_per_rule: {}", avg_symbols_per_rule);
-        //     println!("rule_count: {}", rule_count);
-        //     println!("prob_match: {}", prob_match);
-        // }
-
-        let add_symbol_chance = avg_symbols_per_rule as f32 / symbol_count as f32;
-        assert!(add_symbol_chance < 1.0);
-
-        let mut world_rules = Vec::new();
-        for _i in 0..rule_count {
-            let mut symbols_needed = Vec::new();
-            for symbol in 0..symbol_count {
-                if (random(0, 1000) as f32) < add_symbol_chance*1000.0 { 
-                    symbols_needed.push(symbol);
-                }
-            }
-            if symbols_needed.is_empty() {
-                symbols_needed.push(random(0, symbol_count-1));
-            }
-            let output_symbol = random(0, symbol_count-1);
-            world_rules.push(WorldRule { symbols_needed, output_symbol });
-        }
-
-        assert!( !world_rules.is_empty() );
-
-        World {
-            size: world_size,
-            data: vec![0; world_size.pow(2) as usize],
-            prev_data: vec![0; world_size.pow(2) as usize],
-            cell_changed_flags: vec![true; world_size.pow(2) as usize],
-            neighborhood_changed_flags: vec![true; world_size.pow(2) as usize],
-            symbol_count,
-            symbol_to_color,
-            rules: world_rules,
-        }
-    }
-
-    pub fn _set(&mut self, pos:(u32, u32), value:u32) {
-        let (x, y) = pos;
-        let i = y * self.size + x;
-        self.data[i as usize] = value;
-    }
-
-    pub fn step(&mut self) {
-
-        mem::swap(&mut self.data, &mut self.prev_data);
-
-        self.cell_changed_flags.iter_mut().for_each(|v| *v = false);
-
-        let world_size = self.size;
-        let rules = &self.rules;
-
-        let cell_changed_flags = &mut self.cell_changed_flags;
-        let neighborhood_changed_flags = &mut self.neighborhood_changed_flags;
-        
-        let prev_data = &self.prev_data;
-        let data = &mut self.data;
-
-        data.par_iter_mut()
-        .zip(cell_changed_flags.par_iter_mut())
-        .zip(neighborhood_changed_flags.par_iter()) // <-- don't need iter_mut here.
-        .enumerate()
-        .for_each(|(i, ((cell, cell_changed_flag), neighborhood_changed_flag))| {
-            if !*neighborhood_changed_flag {
-                return;
-            }
-            let x = i as u32 % world_size;
-            let y = (i as u32 - x) / world_size;
-            let current_value = prev_data[i]; // remember, `prev_data` is "current" value because we did a mem:swap at the start of `step()`
-            let next_value = compute_transition(&prev_data, world_size, (x, y), &rules);
-            *cell = next_value;
-            *cell_changed_flag = next_value != current_value;
-        });
-
-        // now we (in effect) run a "erosion" over the `cell_changed_flag` grid to produce the `neighborhood_changed_flag` grid.
-        // more concretely: if a cell and all its neighbors are did not change, then we set the neighborhood_changed flag at that
-        // postition to false.
-        neighborhood_changed_flags.par_iter_mut().enumerate().for_each(|(i, neighborhood_changed_flag)| {
-            let xc = i as u32 % world_size;
-            let yc = (i as u32 - xc) / world_size;
-            for y in (yc as i32 - 1)..(yc as i32 + 2) {
-                for x in (xc as i32 - 1)..(xc as i32 + 2) {
-                    let yy = if y < 0 {world_size-1} else if y as u32 == world_size {0} else {y as u32};
-                    let xx = if x < 0 {world_size-1} else if x as u32 == world_size {0} else {x as u32};
-                    let ii = yy*world_size + xx;
-                    let changed = cell_changed_flags[ii as usize];
-                    if changed {
-                        *neighborhood_changed_flag = true;
-                        return;
-                    }
-                }
-            }
-            *neighborhood_changed_flag = false;
-        });
-
-    }
-
-    pub fn randomize(&mut self) {
-        let mut rng = thread_rng();
-        for i in 0..self.data.len() {
-            let r:f32 = rng.gen();
-            self.data[i as usize] = (r * self.symbol_count as f32).floor() as u32;
-        }
-    }
-
-    pub fn draw_to_buffer(&self, buffer:&mut Vec<u32>) {
-        let world_size = (self.data.len() as f32).sqrt();
-        let window_size = (buffer.len() as f32).sqrt();
-        assert!(world_size.log(2.0) % 1.0 == 0.0 && window_size.log(2.0) % 1.0 == 0.0);
-        assert!(window_size >= world_size);
-
-        let cell_size = (window_size / world_size) as usize;
-        if cell_size == 1 {
-            for i in 0..self.data.len() {
-                let v = self.data[i as usize];
-                let (r, g, b) = self.symbol_to_color[v as usize];
-                buffer[i as usize] = (0 as u32) | (u32::from(r) << 16) | (u32::from(g) << 8) | u32::from(b);
-            }
-        } else {
-            // loop over the "cells":
-            for y in 0..world_size as usize {
-                for x in 0..world_size as usize {
-                    let i = y * (world_size as usize) + x;
-                    let v = self.data[i as usize];
-                    let (r, g, b) = self.symbol_to_color[v as usize];
-                    let rgb_bits = (0 as u32) | (u32::from(r) << 16) | (u32::from(g) << 8) | u32::from(b);
-                    // fill in this cell:
-                    for wy in (y*cell_size)..((y+1)*cell_size) {
-                        for wx in (x*cell_size)..((x+1)*cell_size) {
-                            let wi = wy * (window_size as usize) + wx;
-                            buffer[wi] = rgb_bits;
-                        }
-                    }
-                }
-            }           
-        }
-
-    }
-
-    pub fn _draw_to_console(&self) {
-        use ansi_term::Colour::RGB;
-        use ansi_term::ANSIStrings;
-
-        let mut ansi_characters = Vec::new();
-        for y in 0..self.size {
-            for x in 0..self.size {
-                let i = y*self.size + x;
-                let v = self.data[i as usize];
-                let (r, g, b) = self.symbol_to_color[v as usize];
-                let c = RGB(r, g, b).paint("▓▓");
-                ansi_characters.push(c);
-            }
-            ansi_characters.push(RGB(0, 0, 0).paint("\n"));
-        }
-        println!("{}", ANSIStrings(&ansi_characters));
-    }
-}
-
-fn compute_transition(prev_data: &[u32], world_size:u32, pos:(u32, u32), rules: &[WorldRule]) -> u32 {
-    use std::collections::HashSet;
-
-    unsafe { scratch_counter_1 += 1; }
-
-    // count symbols in neighborhood:
-    let (xc, yc) = pos;
-    let mut symbol_counts_set = HashSet::<u32>::with_capacity(9);
-    for y in (yc as i32 - 1)..(yc as i32 + 2) {
-        for x in (xc as i32 - 1)..(xc as i32 + 2) {
-            let yy = if y < 0 {world_size-1} else if y as u32 == world_size {0} else {y as u32};
-            let xx = if x < 0 {world_size-1} else if x as u32 == world_size {0} else {x as u32};
-            let i = yy*world_size + xx;
-            let v = prev_data[i as usize];
-            symbol_counts_set.insert(v);
-            unsafe { scratch_counter_2 += 1; }
-        }
-    }
-
-    // find first rule that matches:
-    for rule in rules.iter() {
-        let mut found_non_match = false;
-        for symbol in rule.symbols_needed.iter() {
-            unsafe { scratch_counter_3 += 1; }
-            if !symbol_counts_set.contains(&symbol) {
-                found_non_match = true;
-                break;
-            }
-        }
-        if !found_non_match {
-            return rule.output_symbol;
-        }
-    }
-
-    // by default keep the same value:
-    let i = yc*world_size + xc;
-    prev_data[i as usize]
-}
-
-static mut scratch_counter_1: u32 = 0;
-static mut scratch_counter_2: u32 = 0;
-static mut scratch_counter_3: u32 = 0;
+use color_rules_cellular_automata::*;
+#[cfg(feature="config-file")]
+use serde::{Serialize, Deserialize};
 
 fn main() {
 
     #[cfg(not(feature="interactive"))] {
-        println!("# use `cargo run --features \"interactive\" --release` to visually display the worlds (ESC to go to next world; ENTER to replay current world; S to save all frames so far (up to 1000) into gif; P to pause simulation for one second)");
+        println!("# use `cargo run --features \"interactive\" --release` to visually display the worlds (ESC to go to next world; ENTER to replay current world; S to save all frames so far (up to 1000) into gif; A to auto-curate (skip to the next seed that scores above the threshold, adjusted with +/-); P to pause simulation for one second; T to toggle motion trails; M to mutate the seed and BACKSPACE to go back to its parent; C to cycle the palette's hues (, and . to halve/double the speed); D to dim the cells that didn't change; I/J/K/L to roll the view around the edges and O to reset it; [ and ] to start and stop recording a gif of exactly the frames in between; UP/DOWN to change symbol count and LEFT/RIGHT to change avg symbols per rule, replaying the current seed)");
     }
 
 //    remember, goal is to learn rust!
@@ -308,22 +33,342 @@ fn main() {
     let mut last_seed = 0;
     let mut exploration_count = 0;
 
+    let command_line_args: Vec<String> = std::env::args().collect();
+
+    // most of the parameters below can also come from a toml file (see `RunConfig`), with any flags that are given
+    // overriding it. `--dump-config <path>` writes the effective parameters out as such a file.
+    let file_config = match command_line_arg_value(&command_line_args, "--config-file") {
+        Some(path) => load_run_config(&path).unwrap_or_else(|e| panic!("couldn't load --config-file {}: {}", path, e)),
+        None => RunConfig::default(),
+    };
+
+    // stop after this many seeds (e.g. for unattended batch runs). the summary is also printed on ctrl-c.
+    let max_explorations = command_line_arg_value(&command_line_args, "--max-explorations").map(|v| v.parse::<usize>().expect("--max-explorations must be a positive integer"));
+    let search_stats = std::sync::Arc::new(SearchStats::new());
+    {
+        let search_stats = search_stats.clone();
+        ctrlc::set_handler(move || {
+            search_stats.print_summary();
+            std::process::exit(130);
+        }).expect("couldn't install the ctrl-c handler");
+    }
+
+    // replay a particular seed (e.g. from the list below) instead of exploring random ones:
+    let seed_arg = command_line_arg_value(&command_line_args, "--seed").map(|v| parse_seed(&v).unwrap_or_else(|e| panic!("invalid --seed {}: {}", v, e)));
+    if let Some(seed) = seed_arg {
+        last_seed = seed;
+    }
+
     // symbol_count=13, avg_symbols_per_rule=6
     let predefined_seeds_list: Vec::<u64> = vec![];
     let mut predefined_seeds_list_index = 0;
     // ``.trim().split("\n").map(l => l.trim().split(" ").pop()).join(`, `);
-    
-    let command_line_args: Vec<String> = std::env::args().collect();
+
+    // caps how many steps each world runs for (independent of the novelty sampling window):
+    let max_frames_arg = command_line_arg_value(&command_line_args, "--frames").map(|v| v.parse::<usize>().expect("--frames must be a positive integer"));
+
+    // only report worlds that stayed active (i.e. didn't go static) for this many frames (the cap counts as active):
+    #[cfg(not(feature="interactive"))]
+    let min_active_frames = command_line_arg_value(&command_line_args, "--min-active-frames").map(|v| v.parse::<usize>().expect("--min-active-frames must be a positive integer")).unwrap_or(0);
+    #[cfg(not(feature="interactive"))]
+    let max_active_frames = command_line_arg_value(&command_line_args, "--max-active-frames").map(|v| v.parse::<usize>().expect("--max-active-frames must be a positive integer")).unwrap_or(usize::MAX);
+
+    // saves a strip of evenly-spaced frames for each interesting seed into this directory, for quick visual triage:
+    let thumbnail_dir = command_line_arg_value(&command_line_args, "--thumbnail-dir").or_else(|| file_config.export.thumbnail_dir.clone());
+    let thumbnail_samples = command_line_arg_value(&command_line_args, "--thumbnail-samples").map(|v| v.parse::<usize>().expect("--thumbnail-samples must be a positive integer")).unwrap_or(file_config.export.thumbnail_samples);
+    let thumbnail_size = command_line_arg_value(&command_line_args, "--thumbnail-size").map(|v| v.parse::<u32>().expect("--thumbnail-size must be a positive integer")).unwrap_or(file_config.export.thumbnail_size);
+
+    // prints the N most common neighborhoods (which sets of symbols occur together) over each run, for deciding which
+    // rules matter (see `NeighborhoodHistogram`):
+    let neighborhood_report = command_line_arg_value(&command_line_args, "--neighborhood-report").map(|v| v.parse::<usize>().expect("--neighborhood-report must be a positive integer"));
+
+    // adds rules that stop any one symbol from monopolizing a region (see `World::add_repulsion_rules`):
+    let repulsion = file_config.world.repulsion || command_line_args.contains(&"--repulsion".to_string());
+    // biases rule outputs towards symbols that "fit" next to the ones the rule needs, like wang tiles (see `WorldConfig`):
+    let edge_matching = file_config.world.edge_matching || command_line_args.contains(&"--wang-tiles".to_string());
+
+    // only reports a seed if its behavior (see `World::behavior_fingerprint`) hasn't been seen yet this run, so that the
+    // same dynamics under different seeds/params aren't catalogued twice:
+    #[cfg(not(feature="interactive"))]
+    let dedup_behavior = command_line_args.contains(&"--dedup-behavior".to_string());
+    #[cfg(not(feature="interactive"))]
+    let mut seen_fingerprints = std::collections::HashSet::<u64>::new();
+
+    // writes the colors of each exported gif/thumbnail strip into a `.pal` file next to it (see `write_palette`):
+    let palette_sidecar = file_config.export.palette_sidecar || command_line_args.contains(&"--palette-sidecar".to_string());
+
+    // pages through a list of seeds (e.g. the finds of a headless search, in the same format as `--skip-known`) with
+    // the left/right arrow keys, re-running each one from scratch. escape also moves on to the next one.
+    #[cfg(feature="interactive")]
+    let seed_list = command_line_arg_value(&command_line_args, "--interactive-seed-list").map(|path| {
+        let text = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("couldn't read --interactive-seed-list {}: {}", path, e));
+        let seeds = parse_seed_list(&text);
+        assert!(!seeds.is_empty(), "No seeds found in {}.", path);
+        println!("# loaded {} seeds from {}", seeds.len(), path);
+        seeds
+    });
+    #[cfg(feature="interactive")]
+    let mut seed_list_index = 0;
+    #[cfg(feature="interactive")] {
+        if let (Some(seeds), None) = (seed_list.as_ref(), seed_arg) {
+            last_seed = seeds[0];
+        }
+    }
+
+    // M replaces the current seed with a "nearby" one (see `mutate_seed`) that has this many bits flipped, and backspace
+    // goes back to its parent:
+    #[cfg(feature="interactive")]
+    let mutation_bits = command_line_arg_value(&command_line_args, "--mutation-bits").map(|v| v.parse::<u32>().expect("--mutation-bits must be an integer from 1 to 64")).unwrap_or(3);
+    #[cfg(feature="interactive")]
+    let mut lineage: Vec<SeedMutation> = vec![];
+    #[cfg(feature="interactive")]
+    let mut mutation_counts = std::collections::HashMap::<u64, u64>::new(); // so that the nth press on a seed always gives the same child
+
+    // the interactive window's title, with `{placeholders}` for live values (see `TitleField`):
+    #[cfg(feature="interactive")]
+    let default_title_template = if seed_list.is_some() { SEED_LIST_TITLE_TEMPLATE } else { DEFAULT_TITLE_TEMPLATE };
+    #[cfg(feature="interactive")]
+    let title_template = parse_title_template(&command_line_arg_value(&command_line_args, "--title").unwrap_or_else(|| default_title_template.to_string())).unwrap_or_else(|e| panic!("invalid --title: {}", e));
+
+    // saves each exported gif's initial grid into a `.grid` file next to it (see `write_grid`), and `--initial-grid`
+    // starts every world from such a file instead of a random grid:
+    #[cfg(feature="interactive")]
+    let save_initial_grid = command_line_args.contains(&"--save-initial-grid".to_string());
+    let initial_grid_path = command_line_arg_value(&command_line_args, "--initial-grid");
+
+    // makes S start/pause/continue one growing gif per world (see `GifRecorder`) rather than saving the frames so far:
+    #[cfg(feature="interactive")]
+    let append_gif = command_line_args.contains(&"--append-gif".to_string());
+
+    // writes every frame of every world as a png into `<dir>/seed_<seed>/` (see `PngFrameWriter`), for making videos:
+    let png_frames_dir = command_line_arg_value(&command_line_args, "--png-frames").or_else(|| file_config.export.png_frames_dir.clone());
+    let png_upscale = command_line_arg_value(&command_line_args, "--png-upscale").map(|v| v.parse::<u32>().expect("--png-upscale must be a positive integer")).unwrap_or(file_config.export.png_upscale);
+    // draws each cell of exported gifs as an NxN block, since gifs at one pixel per cell are tiny:
+    let gif_upscale = command_line_arg_value(&command_line_args, "--gif-upscale").map(|v| v.parse::<u32>().expect("--gif-upscale must be a positive integer")).unwrap_or(file_config.export.gif_upscale);
+    assert!(gif_upscale > 0, "--gif-upscale must be a positive integer");
+
+    // starts each world from a sparse spark (this fraction of non-background cells, see `World::spark`) instead of dense
+    // noise, which is where a lot of growth and wave dynamics show up:
+    let spark_density = command_line_arg_value(&command_line_args, "--spark-density").map(|v| v.parse::<f32>().expect("--spark-density must be a number from 0 to 1"));
+
+    // burns the seed and frame number into exported animations:
+    let caption_exports = file_config.export.caption || command_line_args.contains(&"--caption".to_string());
+
+    // when the window is shrunk below the world size, crop the world rather than averaging it down to fit:
+    let no_antialias = !file_config.export.antialias || command_line_args.contains(&"--no-antialias".to_string());
+
+    // once the novelty sample is over, recolors the world so that symbols which often turn into each other have similar
+    // hues (see `World::harmonize_palette`):
+    #[cfg(feature="interactive")]
+    let harmonize_palette = command_line_args.contains(&"--harmonize-palette".to_string());
+
+    // shades each cell by an intensity that the rules raise or lower as they fire (see `World::enable_intensity`), with
+    // the amounts picked from the seed:
+    let intensity = command_line_args.contains(&"--intensity".to_string());
+
+    // toggled with T in interactive mode, to render motion trails:
+    let trail_mode = false;
+    #[cfg(feature="interactive")]
+    let mut trail_mode = trail_mode;
+
+    // toggled with C in interactive mode: slowly turns the hues of the palette (see `rotate_hues`) without affecting the
+    // simulation, for finding a nice coloring. , and . halve/double the speed. the palette stays wherever the cycling
+    // is stopped, so that S (with --palette-sidecar) saves it.
+    #[cfg(feature="interactive")]
+    let mut color_cycling = false;
+    #[cfg(feature="interactive")]
+    let mut hue_cycle_speed: f32 = 1.0; // degrees per frame
+
+    // toggled with D in interactive mode, to dim everything that didn't change in the last step (see
+    // `World::change_highlight`):
+    #[cfg(feature="interactive")]
+    let mut change_highlight: Option<f32> = None;
+
+    // moved with I/J/K/L in interactive mode (O resets it), to roll the view around the torus so that the wraparound
+    // seam isn't through the middle of something (see `World::view_offset`). saved gifs are rolled the same way.
+    #[cfg(feature="interactive")]
+    let mut view_offset = (0, 0);
+
+    // these can be changed with the arrow keys in interactive mode:
+    let symbol_count = file_config.world.symbol_count; // normal=13
+    let avg_symbols_per_rule = file_config.world.avg_symbols_per_rule; // remember that there are 9 spaces to match against
+    #[cfg(feature="interactive")]
+    let (mut symbol_count, mut avg_symbols_per_rule) = (symbol_count, avg_symbols_per_rule);
+    // everything else about the worlds stays fixed:
+    let base_config = WorldConfig { repulsion, edge_matching, ..file_config.world.clone() };
+
+    // seeds that have already been found/catalogued, so we don't waste time re-exploring them:
+    let mut known_seeds = command_line_arg_value(&command_line_args, "--skip-known").map(KnownSeeds::load);
+    let mut skipped_known_count = 0;
+
+    // raise the window to stop slower oscillators (period > window) from passing as novel:
+    let change_window = command_line_arg_value(&command_line_args, "--change-window").map(|v| v.parse::<usize>().expect("--change-window must be a positive integer")).unwrap_or(file_config.novelty.change_window);
+    let change_window_gap = command_line_arg_value(&command_line_args, "--change-window-gap").map(|v| v.parse::<usize>().expect("--change-window-gap must be a non-negative integer")).unwrap_or(file_config.novelty.change_window_gap);
+    // lets each world run this many frames before the novelty metrics start, so the initial random soup is ignored:
+    let burn_in_frames = command_line_arg_value(&command_line_args, "--burn-in").map(|v| v.parse::<usize>().expect("--burn-in must be a non-negative integer")).unwrap_or(file_config.novelty.burn_in_frames);
+    let novelty_params = NoveltySearchParams { change_window, change_window_gap, burn_in_frames, ..file_config.novelty.clone() };
+
+    // gives up on seeds that can't pass anyway before their sample is done (see `EarlyDecisionPolicy`). the checkpoints
+    // are comma-separated fractions of the sample, e.g. `--early-checkpoints 0.25,0.5 --early-min-activity 0.005`
+    #[cfg(not(feature="interactive"))]
+    let early_decision = {
+        let checkpoints = command_line_arg_value(&command_line_args, "--early-checkpoints").map(|v| v.split(',').map(|c| c.trim().parse::<f32>().expect("--early-checkpoints must be comma-separated fractions")).collect::<Vec<f32>>());
+        let min_changed_fraction = command_line_arg_value(&command_line_args, "--early-min-activity").map(|v| v.parse::<f32>().expect("--early-min-activity must be a fraction"));
+        if command_line_args.contains(&"--early-abort".to_string()) || checkpoints.is_some() || min_changed_fraction.is_some() {
+            let defaults = EarlyDecisionPolicy::default();
+            Some(EarlyDecisionPolicy { checkpoints: checkpoints.unwrap_or(defaults.checkpoints), min_changed_fraction: min_changed_fraction.unwrap_or(defaults.min_changed_fraction) })
+        } else {
+            None
+        }
+    };
+
+    if let Some(path) = command_line_arg_value(&command_line_args, "--dump-config") {
+        let effective_config = RunConfig {
+            world: WorldConfig { symbol_count, avg_symbols_per_rule, ..base_config.clone() },
+            novelty: novelty_params.clone(),
+            export: ExportOptions {
+                caption: caption_exports,
+                palette_sidecar,
+                antialias: !no_antialias,
+                thumbnail_dir: thumbnail_dir.clone(),
+                thumbnail_samples,
+                thumbnail_size,
+                png_frames_dir: png_frames_dir.clone(),
+                png_upscale,
+                gif_upscale,
+            },
+        };
+        let text = run_config_to_toml(&effective_config).unwrap_or_else(|e| panic!("couldn't write the config: {}", e));
+        std::fs::write(&path, text).unwrap_or_else(|e| panic!("couldn't write --dump-config {}: {}", path, e));
+        println!("# wrote the config to {}", path);
+        return;
+    }
+
+    // pressing A in interactive mode keeps running random seeds headlessly until one scores above this (adjusted with +/-):
+    #[cfg(feature="interactive")]
+    let mut auto_curate_threshold = novelty_params.min_end_cell_diff as f64;
+    #[cfg(feature="interactive")]
+    let mut auto_curate_skipped = 0;
+
+    // re-runs every seed in a catalog file (lines of `<seed> <label> [<symbol count> <avg symbols per rule>]`, where the
+    // label is from `classify_run`) and reports any whose behavior no longer matches, e.g. after an optimization:
+    if let Some(path) = command_line_arg_value(&command_line_args, "--validate-catalog") {
+        let max_frames = max_frames_arg.unwrap_or(novelty_params.sample_end_frame());
+        let all_passed = validate_catalog(&path, &novelty_params, max_frames, &WorldConfig { symbol_count, avg_symbols_per_rule, ..base_config.clone() });
+        std::process::exit(if all_passed { 0 } else { 1 });
+    }
+
+    // follows one cell (--explain-cell x,y, or the middle one) of --explain-seed for the first --explain-steps (10)
+    // steps, printing its neighborhood, the rule that fired and what it turned into each time (see
+    // `World::explain_cell`). --explain-json prints one json object per step instead.
+    if let Some(v) = command_line_arg_value(&command_line_args, "--explain-seed") {
+        let seed = parse_seed(&v).unwrap_or_else(|e| panic!("invalid --explain-seed {}: {}", v, e));
+        let config = WorldConfig { symbol_count, avg_symbols_per_rule, ..base_config.clone() };
+        let pos = command_line_arg_value(&command_line_args, "--explain-cell").map(|v| parse_cell(&v).unwrap_or_else(|e| panic!("invalid --explain-cell {}: {}", v, e))).unwrap_or((config.size / 2, config.size / 2));
+        assert!(pos.0 < config.size && pos.1 < config.size, "--explain-cell must be inside the {}x{} world", config.size, config.size);
+        let steps = command_line_arg_value(&command_line_args, "--explain-steps").map(|v| v.parse::<usize>().expect("--explain-steps must be a positive integer")).unwrap_or(10);
+        let json = command_line_args.contains(&"--explain-json".to_string());
+        let mut world = World::from_config(&config, seed);
+        fill_starting_grid(&mut world, seed, initial_grid_path.as_deref(), spark_density);
+        for _ in 0..steps {
+            let explanation = world.explain_cell(pos);
+            if json {
+                println!("{}", explanation.to_json());
+            } else {
+                println!("{}\n", explanation);
+            }
+            world.step();
+        }
+        return;
+    }
+
+    // runs a seed (--seed, or a random one) twice and checks that every frame comes out the same, as a quick way to confirm
+    // that this build reproduces seeds before starting a long sweep. exits with 1 (after printing the first divergence)
+    // if it doesn't.
+    if command_line_args.contains(&"--self-check".to_string()) {
+        let config = WorldConfig { symbol_count, avg_symbols_per_rule, ..base_config.clone() };
+        let max_frames = max_frames_arg.unwrap_or(novelty_params.sample_end_frame());
+        let seed = seed_arg.unwrap_or_else(rand::random::<u64>);
+        match replay_divergence(&config, seed, max_frames) {
+            None => println!("# self-check passed: seed {} replayed identically for {} frames", seed, max_frames),
+            Some(divergence) => {
+                println!("# self-check failed: seed {} diverged at frame {} ({} cells differ)", seed, divergence.frame, divergence.differing_cells);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // maps out which generation parameters give interesting worlds: runs --seeds-per-cell seeds (counting up from --seed,
+    // or 0) for every symbol count in --sweep-symbols (e.g. `3..8`, inclusive) and average symbols per rule in --sweep-avg
+    // (e.g. `1..4`, in steps of --sweep-avg-step), and prints one csv row per pair, e.g. for a heatmap. pairs that worlds
+    // can't be generated with are skipped.
+    #[cfg(not(feature="interactive"))] {
+        if let Some(symbols_range) = command_line_arg_value(&command_line_args, "--sweep-symbols") {
+            let (first_symbol_count, last_symbol_count) = parse_sweep_range(&symbols_range).unwrap_or_else(|e| panic!("invalid --sweep-symbols: {}", e));
+            let avg_range = command_line_arg_value(&command_line_args, "--sweep-avg").unwrap_or_else(|| avg_symbols_per_rule.to_string());
+            let (first_avg, last_avg) = parse_sweep_range(&avg_range).unwrap_or_else(|e| panic!("invalid --sweep-avg: {}", e));
+            let avg_step = command_line_arg_value(&command_line_args, "--sweep-avg-step").map(|v| v.parse::<f64>().expect("--sweep-avg-step must be a positive number")).unwrap_or(0.5);
+            assert!(avg_step > 0.0, "--sweep-avg-step must be a positive number");
+            let seeds_per_cell = command_line_arg_value(&command_line_args, "--seeds-per-cell").map(|v| v.parse::<u64>().expect("--seeds-per-cell must be a positive integer")).unwrap_or(20);
+            let first_seed = seed_arg.unwrap_or(0);
+            let seeds: Vec<u64> = (0..seeds_per_cell).map(|i| first_seed.wrapping_add(i)).collect();
+
+            let mut params_grid = vec![];
+            for symbol_count in (first_symbol_count.round() as u32)..=(last_symbol_count.round() as u32) {
+                for step in 0..=((last_avg - first_avg) / avg_step + 1e-9).floor() as u32 {
+                    let avg = (first_avg + f64::from(step) * avg_step) as f32;
+                    match check_generation_params(symbol_count, avg) {
+                        Ok(()) => params_grid.push((symbol_count, avg)),
+                        Err(e) => println!("# skipping {} symbols with {} avg symbols per rule: {}", symbol_count, avg, e),
+                    }
+                }
+            }
+            println!("# sweeping {} parameter pairs with {} seeds each", params_grid.len(), seeds.len());
+            let max_frames = max_frames_arg.unwrap_or(novelty_params.sample_end_frame());
+            println!("{}", SweepCell::CSV_HEADER);
+            for cell in parameter_sweep(&base_config, &params_grid, &seeds, &novelty_params, max_frames) {
+                println!("{}", cell.to_csv_row());
+            }
+            return;
+        }
+    }
+
+    // keeps exploring seeds without a window (sweeping up from --seed, or from a random one) and saves a gif of each one
+    // that scores above --min-score into --out-dir, i.e. leave it running overnight and come back to a folder of finds.
+    // it stops after --max-gifs gifs, so that the disk doesn't fill up.
+    #[cfg(not(feature="interactive"))] {
+        if command_line_args.contains(&"--auto-discover".to_string()) {
+            let settings = AutoDiscoverSettings {
+                out_dir: command_line_arg_value(&command_line_args, "--out-dir").unwrap_or_else(|| "gifs".to_string()),
+                min_score: command_line_arg_value(&command_line_args, "--min-score").map(|v| v.parse::<f64>().expect("--min-score must be a number")).unwrap_or(novelty_params.min_end_cell_diff as f64),
+                max_gifs: command_line_arg_value(&command_line_args, "--max-gifs").map(|v| v.parse::<usize>().expect("--max-gifs must be a positive integer")).unwrap_or(100),
+                max_explorations,
+                caption: caption_exports,
+                palette_sidecar,
+                gif_upscale,
+            };
+            let config = WorldConfig { symbol_count, avg_symbols_per_rule, ..base_config.clone() };
+            let max_frames = max_frames_arg.unwrap_or(novelty_params.sample_end_frame());
+            let start_seed = seed_arg.unwrap_or_else(rand::random::<u64>);
+            auto_discover(&config, start_seed, &settings, &novelty_params, early_decision.as_ref(), max_frames, &search_stats);
+            search_stats.print_summary();
+            return;
+        }
+    }
 
     loop {
 
         let window_size: usize;
         let mut frame_buffer: Vec<u32>;
+        #[cfg(feature="interactive")]
+        let mut frame_buffer_size: (usize, usize);
         let mut window: Window;
         #[cfg(feature="interactive")] {
             window_size = 2usize.pow(10);
             frame_buffer = vec![0; window_size.pow(2)];
-            window = Window::new("Emergence", window_size, window_size, WindowOptions::default()).unwrap();
+            frame_buffer_size = (window_size, window_size);
+            window = Window::new("Emergence", window_size, window_size, WindowOptions { resize: true, ..WindowOptions::default() }).unwrap();
         }
 
         // symbol_count=13, avg_symbols_per_rule=6
@@ -369,77 +414,157 @@ fn main() {
         // very cool "factory" thing: 16807459843228653455
         // very cool "growing whirlpools" thing: 8269510888484532536
 
-        let seed = if last_seed == 0 { rand::random::<u64>() } else { last_seed };
+        let mut seed = if last_seed == 0 { rand::random::<u64>() } else { last_seed };
+        if let Some(known_seeds) = known_seeds.as_mut() {
+            known_seeds.reload_if_changed(); // so that finds can be appended to the file mid-run
+            while last_seed == 0 && known_seeds.seeds.contains(&seed) {
+                skipped_known_count += 1;
+                println!("# skipped known seed: {}  ({} skipped so far)", seed, skipped_known_count);
+                seed = rand::random::<u64>();
+            }
+        }
         //let seed = 5009945354920515720;
+        #[cfg(feature="interactive")] {
+            // any other way of changing seeds starts a new lineage:
+            if lineage.last().is_some_and(|m| m.child != seed) {
+                lineage.clear();
+            }
+        }
         //let seed = if last_seed == 0 { predefined_seeds_list[predefined_seeds_list_index] } else { last_seed }; predefined_seeds_list_index += 1; 
 
-        let size = 2u32.pow(9);
-        let symbol_count = 5; // normal=13
-        let avg_symbols_per_rule = 4; // remember that there are 9 spaces to match against
-        let mut world = World::new(size, symbol_count, avg_symbols_per_rule, seed);
-        world.randomize();
+        let config = WorldConfig { symbol_count, avg_symbols_per_rule, ..base_config.clone() };
+        let size = config.size;
+        let mut world = World::from_config(&config, seed);
+        world.antialias = !no_antialias;
+        #[cfg(feature="interactive")] {
+            world.change_highlight = change_highlight;
+            world.view_offset = view_offset;
+        }
+        fill_starting_grid(&mut world, seed, initial_grid_path.as_deref(), spark_density);
+        if intensity {
+            use rand::{Rng, SeedableRng, rngs::StdRng};
+            let mut rng = StdRng::seed_from_u64(seed ^ 0x5348_4144_494e_4721); // not the rule generator's stream
+            let deltas = world.rules().iter().map(|_| rng.gen_range(-48i8, 49)).collect();
+            world.enable_intensity(deltas).unwrap();
+        }
+        let initial_data = world.data.clone();
+        #[cfg(not(feature="interactive"))]
+        let initial_world = if dedup_behavior { Some(world.clone()) } else { None };
+        if trail_mode {
+            world.track_last_changed();
+        }
         let now = Instant::now();
         let mut count = 0;
 
-        let mut unique_frame_hashes = std::collections::HashSet::new();
-
-        // we compare these at the end and if they're the same then that likely means
-        // that the changes are just lots of little repeating oscillations that,
-        // when "muliplied" together cause the frames to be unique (thus giving us a false positive).
-        let mut last_frames_cell_changes_anded_1 = vec![false; size.pow(2) as usize]; // second last batch of 5 frames, ANDed together
-        let mut last_frames_cell_changes_anded_2 = vec![false; size.pow(2) as usize]; // last batch of 5 frames, ANDed together
+        let mut novelty = NoveltyTracker::new(&novelty_params, size.pow(2) as usize);
 
         let mut already_printed_details = false;
-        let print_details = |unique_frame_hashes_len, seed, cell_change_diff_count| println!("unique: {}  cell_change_diff_count: {}  seed: {}", unique_frame_hashes_len, cell_change_diff_count, seed);
-
-        let sample_frame_count = 400;
-        let min_end_cell_diff = 25;
-
-        let mut frames = Vec::<Vec<u32>>::new();
+        let print_details = |unique_frame_hashes_len, seed, cell_change_diff_count, frames_run, dominant_fraction| println!("unique: {}  cell_change_diff_count: {}  frames: {}  dominant: {:.3}  seed: {}", unique_frame_hashes_len, cell_change_diff_count, frames_run, dominant_fraction, seed);
+
+        #[cfg(not(feature="interactive"))]
+        let sample_frame_count = novelty_params.sample_frame_count;
+        let sample_end_frame = novelty_params.sample_end_frame();
+        #[cfg(not(feature="interactive"))]
+        let max_frames = max_frames_arg.unwrap_or(sample_end_frame);
+        // metrics are only ever computed over the first `sample_frame_count` frames after the burn-in, even if we keep stepping after that:
+        #[cfg(not(feature="interactive"))]
+        let mut sampled_details: Option<(usize, u32)> = None;
+
+        #[cfg(feature="interactive")]
+        let mut frames = Vec::<Vec<(u32, u32)>>::new(); // run-length encoded, since there can be up to 1000 of them
+        #[cfg(feature="interactive")]
+        let mut recorder: Option<GifRecorder> = None; // only with --append-gif
+        #[cfg(feature="interactive")]
+        let mut recording = false;
+        // [ starts a gif of exactly the frames from then on, and ] finishes it (dropping it, e.g. on a new seed, does too):
+        #[cfg(feature="interactive")]
+        let mut marker_recorder = MarkerRecorder::new();
+        #[cfg(feature="interactive")]
+        let mut transitions = if harmonize_palette { Some(TransitionMatrix::new(symbol_count)) } else { None };
+        #[cfg(feature="interactive")]
+        let mut hue_cycle: Option<HueCycle> = None;
+
+        #[cfg(feature="stats")]
+        let mut step_latency = LatencyHistogram::new();
+
+        let mut neighborhoods = neighborhood_report.map(|_| NeighborhoodHistogram::new());
+
+        #[cfg(not(feature="interactive"))]
+        let mut png_frames = png_frames_dir.as_ref().map(|dir| {
+            PngFrameWriter::new(std::path::Path::new(dir).join(format!("seed_{}", seed)), png_upscale, max_frames).unwrap_or_else(|e| panic!("couldn't create --png-frames directory: {}", e))
+        });
 
         loop {
 
+            #[cfg(feature="stats")]
+            let step_start = Instant::now();
             world.step();
+            #[cfg(feature="stats")]
+            step_latency.record(step_start.elapsed());
+
+            #[cfg(not(feature="interactive"))] {
+                if let Some(writer) = png_frames.as_mut() {
+                    if let Err(e) = writer.write_frame(&world) {
+                        println!("# couldn't save png frame {} for seed {}: {}", writer.frame_count(), seed, e);
+                        png_frames = None;
+                    }
+                }
+            }
 
-            // unsafe { println!("scratch_counter_1: {}", scratch_counter_1); }
-            // unsafe { println!("scratch_counter_2: {}", scratch_counter_2); }
-            // unsafe { println!("scratch_counter_3: {}", scratch_counter_3); }
-            unsafe { scratch_counter_1 = 0; }
-            unsafe { scratch_counter_2 = 0; }
-            unsafe { scratch_counter_3 = 0; }
             //println!("changes: {}", world.data.iter().zip(world.prev_data.iter()).filter(|(a,b)| *a != *b).count());
             
             //world._draw_to_console();
             //std::thread::sleep(Duration::from_millis(1000));
 
             #[cfg(feature="interactive")] {
-                world.draw_to_buffer(&mut frame_buffer);
-                window.update_with_buffer(&frame_buffer).unwrap();
-                if frames.len() < 1000 { frames.push(world.data.clone()); }
-            }
-
-            if count <= sample_frame_count {
-                unique_frame_hashes.insert( calculate_vec_hash(&world.data) );
-            }
-            if count > sample_frame_count-10 && count <= sample_frame_count-5 {
-                world.cell_changed_flags.iter().enumerate().for_each(|(i, v)| {
-                    if *v {
-                        last_frames_cell_changes_anded_1[i] = true;
+                let (width, height) = window.get_size();
+                if width > 0 && height > 0 { // e.g. minimized
+                    if (width, height) != frame_buffer_size {
+                        // a fresh (black) buffer, so any area outside the world stays cleared:
+                        frame_buffer = vec![0; width * height];
+                        frame_buffer_size = (width, height);
                     }
-                });
-            }
-            if count > sample_frame_count-5 && count <= sample_frame_count {
-                world.cell_changed_flags.iter().enumerate().for_each(|(i, v)| {
-                    if *v {
-                        last_frames_cell_changes_anded_2[i] = true;
+                    if color_cycling {
+                        // always from the starting palette, so that rounding errors don't build up:
+                        let (base_palette, degrees) = hue_cycle.get_or_insert_with(|| (world.symbol_to_color.clone(), 0.0));
+                        *degrees = (*degrees + hue_cycle_speed) % 360.0;
+                        world.symbol_to_color = rotate_hues(base_palette, *degrees);
                     }
-                });
+                    if trail_mode {
+                        world.draw_trails_to_buffer(&mut frame_buffer, width, height, 30);
+                    } else {
+                        world.draw_to_buffer(&mut frame_buffer, width, height);
+                    }
+                    window.update_with_buffer(&frame_buffer).unwrap();
+                } else {
+                    window.update();
+                }
+                if frames.len() < 1000 { frames.push(world.to_rle()); }
+                if let Err(e) = marker_recorder.add_frame(&roll_grid(&world.data, size, world.view_offset)) {
+                    println!("# couldn't record frame for seed {}: {}", seed, e);
+                    marker_recorder.stop();
+                }
+                if recording {
+                    if let Err(e) = recorder.as_mut().unwrap().add_frame(&roll_grid(&world.data, size, world.view_offset)) {
+                        println!("# couldn't record frame for seed {}: {}", seed, e);
+                        recording = false;
+                    }
+                }
+            }
+
+            novelty.record_frame(count, &world);
+            if let Some(neighborhoods) = neighborhoods.as_mut() {
+                neighborhoods.record(&world);
             }
 
             #[cfg(feature="interactive")] {
                 if window.is_key_down(minifb::Key::Escape) {
                     std::thread::sleep(Duration::from_millis(500));
                     last_seed = 0;
+                    if let Some(seeds) = seed_list.as_ref() {
+                        seed_list_index = (seed_list_index + 1) % seeds.len();
+                        last_seed = seeds[seed_list_index];
+                    }
                     break;
                 }
                 if window.is_key_down(minifb::Key::Enter) {
@@ -447,42 +572,237 @@ fn main() {
                     last_seed = seed;
                     break;
                 }
+                if window.is_key_pressed(minifb::Key::M, minifb::KeyRepeat::No) {
+                    let index = mutation_counts.entry(seed).or_insert(0);
+                    let mutation = mutate_seed(seed, *index, mutation_bits);
+                    *index += 1;
+                    println!("# seed {} is {}", mutation.child, mutation);
+                    last_seed = mutation.child;
+                    lineage.push(mutation);
+                    break;
+                }
+                if window.is_key_pressed(minifb::Key::Backspace, minifb::KeyRepeat::No) {
+                    if let Some(mutation) = lineage.pop() {
+                        last_seed = mutation.parent;
+                        break;
+                    }
+                }
+                if window.is_key_pressed(minifb::Key::T, minifb::KeyRepeat::No) {
+                    trail_mode = !trail_mode;
+                    world.track_last_changed();
+                }
+                if window.is_key_pressed(minifb::Key::D, minifb::KeyRepeat::No) {
+                    change_highlight = if change_highlight.is_some() { None } else { Some(0.3) };
+                    world.change_highlight = change_highlight;
+                }
+                let view_step = (size as i32 / 32).max(1);
+                for (key, (dx, dy)) in [(minifb::Key::J, (-1, 0)), (minifb::Key::L, (1, 0)), (minifb::Key::I, (0, -1)), (minifb::Key::K, (0, 1))].iter() {
+                    if window.is_key_pressed(*key, minifb::KeyRepeat::Yes) {
+                        view_offset = ((view_offset.0 + dx * view_step).rem_euclid(size as i32), (view_offset.1 + dy * view_step).rem_euclid(size as i32));
+                        world.view_offset = view_offset;
+                    }
+                }
+                if window.is_key_pressed(minifb::Key::O, minifb::KeyRepeat::No) {
+                    view_offset = (0, 0);
+                    world.view_offset = view_offset;
+                }
+                if window.is_key_pressed(minifb::Key::LeftBracket, minifb::KeyRepeat::No) && !marker_recorder.is_recording() {
+                    let path = format!("./gifs/symbols_{}--seed_{}--from_frame_{}.gif", symbol_count, seed, count);
+                    let caption_seed = if caption_exports { Some(seed) } else { None };
+                    match marker_recorder.start(&path, size, &world.symbol_to_color, caption_seed, gif_upscale) {
+                        Ok(()) => println!("# recording {}, press ] to stop", path),
+                        Err(e) => println!("# couldn't start gif for seed {}: {}", seed, e),
+                    }
+                }
+                if window.is_key_pressed(minifb::Key::RightBracket, minifb::KeyRepeat::No) {
+                    if let Some(frame_count) = marker_recorder.stop() {
+                        println!("# saved {} frames", frame_count);
+                    }
+                }
+                if window.is_key_pressed(minifb::Key::C, minifb::KeyRepeat::No) {
+                    color_cycling = !color_cycling;
+                    hue_cycle = None; // restarting picks up from wherever it was stopped
+                    println!("# color cycling {} ({} degrees per frame)", if color_cycling { "on" } else { "off" }, hue_cycle_speed);
+                }
+                if window.is_key_pressed(minifb::Key::Comma, minifb::KeyRepeat::Yes) {
+                    hue_cycle_speed /= 2.0;
+                }
+                if window.is_key_pressed(minifb::Key::Period, minifb::KeyRepeat::Yes) {
+                    hue_cycle_speed = (hue_cycle_speed * 2.0).min(180.0);
+                }
                 if window.is_key_down(minifb::Key::P) {
                     //println!("p key down: {}", window.is_key_down(minifb::Key::P));
                     std::thread::sleep(Duration::from_millis(1000));
                 }
-                if window.is_key_down(minifb::Key::S) {
+                if let Some(r) = recorder.as_ref().filter(|_| append_gif && window.is_key_pressed(minifb::Key::S, minifb::KeyRepeat::No)) {
+                    recording = !recording;
+                    println!("# {} gif ({} frames so far)", if recording { "continuing" } else { "paused" }, r.frame_count());
+                } else if (append_gif && window.is_key_pressed(minifb::Key::S, minifb::KeyRepeat::No)) || (!append_gif && window.is_key_down(minifb::Key::S)) {
                     println!("SAVING GIF");
                     let filename = format!("symbols_{}--seed_{}", symbol_count, seed.to_string());
-                    make_gif_from_frames(&frames, &world.symbol_to_color, &filename);
+                    let caption_seed = if caption_exports { Some(seed) } else { None };
+                    if append_gif {
+                        // starts with the frames so far, then keeps adding new ones until S is pressed again:
+                        let started = GifRecorder::create_upscaled(format!("./gifs/{}.gif", filename), size, &world.symbol_to_color, caption_seed, gif_upscale).and_then(|mut r| {
+                            frames.iter().try_for_each(|f| r.add_frame(&roll_grid(&rle_decode(f), size, view_offset)))?;
+                            Ok(r)
+                        });
+                        match started {
+                            Ok(r) => {
+                                println!("# recording gif, press S to pause/continue");
+                                recorder = Some(r);
+                                recording = true;
+                            }
+                            Err(e) => println!("# couldn't start gif for seed {}: {}", seed, e),
+                        }
+                    } else {
+                        let decoded_frames: Vec<Vec<u32>> = frames.iter().map(|f| roll_grid(&rle_decode(f), size, view_offset)).collect();
+                        make_gif_from_frames(&decoded_frames, &world.symbol_to_color, &filename, caption_seed, gif_upscale);
+                    }
+                    if palette_sidecar {
+                        if let Err(e) = write_palette(format!("./gifs/{}.pal", filename), &world.symbol_to_color) {
+                            println!("# couldn't save palette for seed {}: {}", seed, e);
+                        }
+                    }
+                    if save_initial_grid {
+                        if let Err(e) = write_grid(format!("./gifs/{}.grid", filename), size, &initial_data) {
+                            println!("# couldn't save initial grid for seed {}: {}", seed, e);
+                        }
+                    }
                 }
 
-                window.set_title(&count.to_string());
-            }
-            count += 1;
+                if window.is_key_down(minifb::Key::A) {
+                    // keep trying random seeds headlessly until one is worth a human looking at:
+                    auto_curate_skipped = 0;
+                    loop {
+                        let candidate_seed = rand::random::<u64>();
+                        // (seeded the same way as the main loop does, so the accepted seed replays this exact grid)
+                        let mut candidate = World::from_config(&config, candidate_seed);
+                        fill_starting_grid(&mut candidate, candidate_seed, initial_grid_path.as_deref(), spark_density);
+                        let summary = run_headless(&mut candidate, candidate_seed, &novelty_params, sample_end_frame);
+                        if NoveltyFitness.score(&summary) > auto_curate_threshold {
+                            println!("# auto-curate skipped {} seeds", auto_curate_skipped);
+                            last_seed = candidate_seed;
+                            break;
+                        }
+                        auto_curate_skipped += 1;
+                        window.set_title(&format!("auto-curating... skipped {} seeds", auto_curate_skipped));
+                        window.update();
+                    }
+                    break;
+                }
+                if window.is_key_pressed(minifb::Key::Equal, minifb::KeyRepeat::Yes) {
+                    auto_curate_threshold += 5.0;
+                }
+                if window.is_key_pressed(minifb::Key::Minus, minifb::KeyRepeat::Yes) {
+                    auto_curate_threshold = (auto_curate_threshold - 5.0).max(0.0);
+                }
 
-            let mut there_were_changes = false;
-            for changed in world.cell_changed_flags.iter() {
-                if *changed {
-                    there_were_changes = true;
+                // with a seed list, left/right move through it (wrapping around at either end) instead:
+                if let Some(seeds) = seed_list.as_ref() {
+                    let step = if window.is_key_down(minifb::Key::Right) { Some(1) } else if window.is_key_down(minifb::Key::Left) { Some(seeds.len() - 1) } else { None };
+                    if let Some(step) = step {
+                        seed_list_index = (seed_list_index + step) % seeds.len();
+                        std::thread::sleep(Duration::from_millis(200));
+                        last_seed = seeds[seed_list_index];
+                        break;
+                    }
+                }
+
+                // change the generation parameters and replay the same seed under them:
+                let arrow_keys = if seed_list.is_some() { &[minifb::Key::Up, minifb::Key::Down][..] } else { &[minifb::Key::Up, minifb::Key::Down, minifb::Key::Left, minifb::Key::Right][..] };
+                if let Some(key) = arrow_keys.iter().find(|k| window.is_key_down(**k)) {
+                    match key {
+                        minifb::Key::Up => symbol_count = (symbol_count + 1).min(64),
+                        minifb::Key::Down => symbol_count = (symbol_count - 1).max(2),
+                        minifb::Key::Right => avg_symbols_per_rule += 0.5,
+                        _ => avg_symbols_per_rule -= 0.5,
+                    }
+                    // must stay positive and below the symbol count (see `World::new`):
+                    avg_symbols_per_rule = clamp_avg_symbols_per_rule(avg_symbols_per_rule, symbol_count);
+                    std::thread::sleep(Duration::from_millis(200));
+                    last_seed = seed;
                     break;
                 }
+
+                let title = render_title(&title_template, |field| match field {
+                    TitleField::Seed => seed.to_string(),
+                    TitleField::Frame => count.to_string(),
+                    TitleField::Fps => format!("{:.1}", count as f64 / now.elapsed().as_secs_f64()),
+                    TitleField::Unique => novelty.unique_frame_count().to_string(),
+                    TitleField::Dominant => format!("{:.3}", world.dominant_fraction()),
+                    TitleField::Symbols => symbol_count.to_string(),
+                    TitleField::AvgSymbolsPerRule => avg_symbols_per_rule.to_string(),
+                    TitleField::Threshold => auto_curate_threshold.to_string(),
+                    TitleField::Skipped => auto_curate_skipped.to_string(),
+                    TitleField::Derivation => lineage.last().map_or(String::new(), |m| format!("  {}", m)),
+                    TitleField::Recording => if marker_recorder.is_recording() { format!("  recording ({} frames)", marker_recorder.frame_count()) } else { String::new() },
+                    TitleField::ListPosition => seed_list.as_ref().map_or("-".to_string(), |seeds| format!("{}/{}", seed_list_index + 1, seeds.len())),
+                });
+                window.set_title(&title);
             }
+            count += 1;
+
+            let there_were_changes = !world.is_static();
 
             //if count == 100 { println!("{}", now.elapsed().as_millis()); }
-            if count == sample_frame_count || !there_were_changes {
-                #[cfg(not(feature="interactive"))] {
-                    let cell_change_diff_count = bool_vec_diff_count(&last_frames_cell_changes_anded_1, &last_frames_cell_changes_anded_2);
-                    if unique_frame_hashes.len() == sample_frame_count && cell_change_diff_count > min_end_cell_diff {
-                        print_details(unique_frame_hashes.len(), seed, cell_change_diff_count);
+            #[cfg(not(feature="interactive"))] {
+                let aborted = early_decision.as_ref().is_some_and(|p| p.should_abort(count, &novelty_params, &novelty, &world));
+                if sampled_details.is_none() && (count == sample_end_frame || count == max_frames || !there_were_changes || aborted) {
+                    sampled_details = Some((novelty.unique_frame_count(), novelty.cell_change_diff_count()));
+                }
+                if count == max_frames || !there_were_changes || aborted {
+                    let (unique_frame_count, cell_change_diff_count) = sampled_details.unwrap();
+                    let (rule_count, avg_rule_length) = world.rule_complexity();
+                    let summary = RunSummary {
+                        seed,
+                        sample_frame_count,
+                        unique_frame_count,
+                        cell_change_diff_count,
+                        active_frames: count,
+                        wavefront: novelty.wavefront(),
+                        rule_count,
+                        avg_rule_length,
+                        #[cfg(feature="stats")]
+                        step_latency: step_latency.clone(),
+                    };
+                    let active_long_enough = summary.active_frames >= min_active_frames && summary.active_frames <= max_active_frames;
+                    // (the fingerprint is only taken for seeds that would otherwise be reported, since it re-runs the world)
+                    let mut is_new_behavior = || initial_world.as_ref().map_or(true, |w| seen_fingerprints.insert(w.behavior_fingerprint(200)));
+                    if NoveltyFitness.score(&summary) > novelty_params.min_end_cell_diff as f64 && active_long_enough && is_new_behavior() {
+                        print_details(unique_frame_count, seed, cell_change_diff_count, count, world.dominant_fraction());
+                        if let Some(wavefront) = summary.wavefront {
+                            println!("# seed {} looks like a travelling front: ({:.2}, {:.2}) cells per frame (coherence {:.2})", seed, wavefront.velocity.0, wavefront.velocity.1, wavefront.coherence);
+                        }
+                        search_stats.interesting.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        if let Some(dir) = &thumbnail_dir {
+                            let out = std::path::Path::new(dir).join(format!("seed_{}.png", seed));
+                            if let Err(e) = render_thumbnail_strip(&config, seed, &initial_data, count, thumbnail_samples, thumbnail_size, &out) {
+                                println!("# couldn't save thumbnail strip for seed {}: {}", seed, e);
+                            }
+                            if palette_sidecar {
+                                if let Err(e) = write_palette(out.with_extension("pal"), &world.symbol_to_color) {
+                                    println!("# couldn't save palette for seed {}: {}", seed, e);
+                                }
+                            }
+                        }
                     }
                     already_printed_details = true;
                     break;
                 }
-                #[cfg(feature="interactive")] {
+            }
+            #[cfg(feature="interactive")] {
+                if let Some(matrix) = transitions.as_mut() {
+                    matrix.record(&world);
+                    if count == sample_end_frame || !there_were_changes {
+                        world.harmonize_palette(matrix);
+                        transitions = None;
+                        hue_cycle = None; // so the cycling carries on from the harmonized palette
+                    }
+                }
+                if count == sample_end_frame || !there_were_changes {
                     if !already_printed_details {
-                        let cell_change_diff_count = bool_vec_diff_count(&last_frames_cell_changes_anded_1, &last_frames_cell_changes_anded_2);
-                        print_details(unique_frame_hashes.len(), seed, cell_change_diff_count);
+                        print_details(novelty.unique_frame_count(), seed, novelty.cell_change_diff_count(), count, world.dominant_fraction());
                     }
                     already_printed_details = true;
                 }
@@ -490,70 +810,488 @@ fn main() {
         }
 
         exploration_count += 1;
+        search_stats.explored.store(exploration_count, std::sync::atomic::Ordering::Relaxed);
+
+        #[cfg(feature="stats")] {
+            println!("# step latency for seed {}: {}", seed, step_latency);
+        }
 
         if !already_printed_details {
-            let cell_change_diff_count = bool_vec_diff_count(&last_frames_cell_changes_anded_1, &last_frames_cell_changes_anded_2);
-            print_details(unique_frame_hashes.len(), seed, cell_change_diff_count);
+            print_details(novelty.unique_frame_count(), seed, novelty.cell_change_diff_count(), count, world.dominant_fraction());
+        }
+
+        if let (Some(neighborhoods), Some(n)) = (neighborhoods.as_ref(), neighborhood_report) {
+            println!("# {} distinct neighborhoods for seed {}, most common:", neighborhoods.distinct_count(), seed);
+            for (mask, fraction) in neighborhoods.top(n) {
+                let symbols: Vec<u32> = (0..64).filter(|s| mask & (1 << s) != 0).collect();
+                println!("#   {:?}: {:.2}%", symbols, fraction * 100.0);
+            }
         }
 
         if command_line_args.contains(&"benchmark".to_string()) {
             println!("{}", exploration_count);
         }
 
+        // there's nothing else to do with a single seed when there's no window to replay it in:
+        #[cfg(not(feature="interactive"))] {
+            if seed_arg.is_some() {
+                break;
+            }
+        }
+
+        if max_explorations.is_some_and(|max| exploration_count >= max) {
+            break;
+        }
+
+    }
+
+    search_stats.print_summary();
+}
+
+// shared with the ctrl-c handler, so that an interrupted run still reports how it went
+struct SearchStats {
+    started: Instant,
+    explored: std::sync::atomic::AtomicUsize,
+    interesting: std::sync::atomic::AtomicUsize, // seeds that passed the (non-interactive) filters and were printed
+}
+
+impl SearchStats {
+    fn new() -> SearchStats {
+        SearchStats { started: Instant::now(), explored: std::sync::atomic::AtomicUsize::new(0), interesting: std::sync::atomic::AtomicUsize::new(0) }
+    }
+
+    fn print_summary(&self) {
+        use std::sync::atomic::Ordering;
+        println!("# explored {} seeds, found {} interesting, in {:.1}s", self.explored.load(Ordering::Relaxed), self.interesting.load(Ordering::Relaxed), self.started.elapsed().as_secs_f64());
+    }
+}
+
+struct KnownSeeds {
+    path: String,
+    modified: Option<SystemTime>,
+    seeds: std::collections::HashSet<u64>,
+}
+
+impl KnownSeeds {
+    fn load(path:String) -> KnownSeeds {
+        let mut known_seeds = KnownSeeds { path, modified: None, seeds: std::collections::HashSet::new() };
+        known_seeds.reload_if_changed();
+        println!("# loaded {} known seeds from {}", known_seeds.seeds.len(), known_seeds.path);
+        known_seeds
+    }
+
+    fn reload_if_changed(&mut self) {
+        let modified = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if modified.is_some() && modified == self.modified {
+            return;
+        }
+        let text = std::fs::read_to_string(&self.path).unwrap_or_else(|e| panic!("couldn't read {}: {}", self.path, e));
+        self.seeds = parse_known_seeds(&text);
+        self.modified = modified;
+    }
+}
+
+// the seed is the last thing on each line, so both plain seed lists and this program's own output can be used:
+fn parse_known_seeds(text:&str) -> std::collections::HashSet<u64> {
+    parse_seed_list(text).into_iter().collect()
+}
+
+// like `parse_known_seeds`, but keeps the order of the file (dropping repeats) for paging through it:
+fn parse_seed_list(text:&str) -> Vec<u64> {
+    let mut seen = std::collections::HashSet::new();
+    text.lines().filter_map(|l| parse_seed(l.split_whitespace().last()?).ok()).filter(|seed| seen.insert(*seed)).collect()
+}
+
+// accepts plain decimal, `0x`-prefixed hex, and either with `_` separators (e.g. `906_339_142_304_154_875`)
+fn parse_seed(s:&str) -> Result<u64, std::num::ParseIntError> {
+    let s = s.trim().replace('_', "");
+    if s.starts_with("0x") || s.starts_with("0X") {
+        u64::from_str_radix(&s[2..], 16)
+    } else {
+        s.parse::<u64>()
+    }
+}
+
+#[cfg(any(feature="interactive", test))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TitleField {
+    Seed,
+    Frame,
+    Fps,
+    Unique, // unique frames so far (of the novelty sample)
+    Dominant, // fraction of the grid held by the most common symbol
+    Symbols,
+    AvgSymbolsPerRule,
+    Threshold, // auto-curate threshold
+    Skipped, // seeds skipped by the last auto-curate
+    ListPosition, // e.g. "3/17" with `--interactive-seed-list`, otherwise "-"
+    Derivation, // how the seed was mutated from its parent (empty if it wasn't)
+    Recording, // how many frames have been recorded since [ was pressed (empty when not recording)
+}
+
+#[cfg(any(feature="interactive", test))]
+#[derive(Clone, Debug, PartialEq)]
+enum TitlePart {
+    Text(String),
+    Field(TitleField),
+}
+
+#[cfg(any(feature="interactive", test))]
+const DEFAULT_TITLE_TEMPLATE: &str = "{frame}  symbols: {symbols}  avg symbols per rule: {avg_symbols_per_rule}  auto-curate threshold: {threshold} (skipped {skipped}){derivation}{recording}";
+#[cfg(any(feature="interactive", test))]
+const SEED_LIST_TITLE_TEMPLATE: &str = "{list_position}  seed: {seed}  {frame}  symbols: {symbols}  avg symbols per rule: {avg_symbols_per_rule}{derivation}{recording}";
+
+// parsed once up front, so that a typo is reported at startup rather than showing up in the title
+#[cfg(any(feature="interactive", test))]
+fn parse_title_template(template:&str) -> Result<Vec<TitlePart>, String> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            parts.push(TitlePart::Text(rest[..start].to_string()));
+        }
+        let end = rest[start..].find('}').ok_or_else(|| format!("unclosed `{{` in {:?}", template))? + start;
+        let field = match &rest[start+1..end] {
+            "seed" => TitleField::Seed,
+            "frame" => TitleField::Frame,
+            "fps" => TitleField::Fps,
+            "unique" => TitleField::Unique,
+            "dominant" => TitleField::Dominant,
+            "symbols" => TitleField::Symbols,
+            "avg_symbols_per_rule" => TitleField::AvgSymbolsPerRule,
+            "threshold" => TitleField::Threshold,
+            "skipped" => TitleField::Skipped,
+            "list_position" => TitleField::ListPosition,
+            "derivation" => TitleField::Derivation,
+            "recording" => TitleField::Recording,
+            name => return Err(format!("unknown placeholder {{{}}} (expected one of seed, frame, fps, unique, dominant, symbols, avg_symbols_per_rule, threshold, skipped, list_position, derivation, recording)", name)),
+        };
+        parts.push(TitlePart::Field(field));
+        rest = &rest[end+1..];
+    }
+    if !rest.is_empty() {
+        parts.push(TitlePart::Text(rest.to_string()));
+    }
+    Ok(parts)
+}
+
+// `value` is only called for the fields the template actually uses
+#[cfg(any(feature="interactive", test))]
+fn render_title<F: Fn(TitleField) -> String>(parts:&[TitlePart], value:F) -> String {
+    parts.iter().map(|part| match part {
+        TitlePart::Text(text) => text.clone(),
+        TitlePart::Field(field) => value(*field),
+    }).collect()
+}
+
+struct CatalogEntry {
+    seed: u64,
+    label: String,
+    symbol_count: Option<u32>,
+    avg_symbols_per_rule: Option<f32>,
+}
+
+// blank lines and `#` comments are skipped
+fn parse_catalog(text:&str) -> Result<Vec<CatalogEntry>, String> {
+    let mut entries = Vec::new();
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() != 2 && parts.len() != 4 {
+            return Err(format!("line {}: expected `<seed> <label> [<symbol count> <avg symbols per rule>]`", line_index + 1));
+        }
+        let seed = parse_seed(parts[0]).map_err(|e| format!("line {}: invalid seed {}: {}", line_index + 1, parts[0], e))?;
+        let (symbol_count, avg_symbols_per_rule) = if parts.len() == 4 {
+            let symbol_count = parts[2].parse::<u32>().map_err(|e| format!("line {}: invalid symbol count: {}", line_index + 1, e))?;
+            let avg_symbols_per_rule = parts[3].parse::<f32>().map_err(|e| format!("line {}: invalid avg symbols per rule: {}", line_index + 1, e))?;
+            (Some(symbol_count), Some(avg_symbols_per_rule))
+        } else {
+            (None, None)
+        };
+        entries.push(CatalogEntry { seed, label: parts[1].to_string(), symbol_count, avg_symbols_per_rule });
     }
+    Ok(entries)
+}
+
+// `base_config` is used for everything that the catalog entries don't specify
+fn validate_catalog(path:&str, novelty_params:&NoveltySearchParams, max_frames:usize, base_config:&WorldConfig) -> bool {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|e| panic!("couldn't read {}: {}", path, e));
+    let entries = parse_catalog(&text).unwrap_or_else(|e| panic!("invalid catalog {}: {}", path, e));
+    let mut failures = 0;
+    for entry in entries.iter() {
+        let config = WorldConfig {
+            symbol_count: entry.symbol_count.unwrap_or(base_config.symbol_count),
+            avg_symbols_per_rule: entry.avg_symbols_per_rule.unwrap_or(base_config.avg_symbols_per_rule),
+            ..base_config.clone()
+        };
+        let mut world = World::from_config(&config, entry.seed);
+        world.randomize_from_seed(entry.seed);
+        let summary = run_headless(&mut world, entry.seed, novelty_params, max_frames);
+        let label = classify_run(&summary, novelty_params);
+        if label == entry.label {
+            println!("pass  seed: {}  label: {}", entry.seed, label);
+        } else {
+            failures += 1;
+            println!("FAIL  seed: {}  expected: {}  got: {}", entry.seed, entry.label, label);
+        }
+    }
+    println!("# {} of {} catalogued seeds passed", entries.len() - failures, entries.len());
+    failures == 0
+}
+
+#[cfg(not(feature="interactive"))]
+struct AutoDiscoverSettings {
+    out_dir: String,
+    min_score: f64, // as scored by `NoveltyFitness`
+    max_gifs: usize,
+    max_explorations: Option<usize>,
+    caption: bool,
+    palette_sidecar: bool,
+    gif_upscale: u32,
+}
+
+#[cfg(not(feature="interactive"))]
+fn auto_discover(config:&WorldConfig, start_seed:u64, settings:&AutoDiscoverSettings, novelty_params:&NoveltySearchParams, early_decision:Option<&EarlyDecisionPolicy>, max_frames:usize, search_stats:&SearchStats) {
+    use std::sync::atomic::Ordering;
+
+    std::fs::create_dir_all(&settings.out_dir).unwrap_or_else(|e| panic!("couldn't create --out-dir {}: {}", settings.out_dir, e));
+    println!("# auto-discovering from seed {} into {} (pass it as --seed to pick up from there)", start_seed, settings.out_dir);
+    let (mut explored, mut saved) = (0, 0);
+    let mut seed = start_seed;
+    while saved < settings.max_gifs && settings.max_explorations.map_or(true, |max| explored < max) {
+        let mut world = World::from_config(config, seed);
+        world.randomize_from_seed(seed);
+        let summary = run_headless_with_policy(&mut world, seed, novelty_params, max_frames, early_decision);
+        explored += 1;
+        search_stats.explored.store(explored, Ordering::Relaxed);
+
+        let score = NoveltyFitness.score(&summary);
+        if score > settings.min_score {
+            // replay it from the same (seeded) starting grid, recording as it goes:
+            let mut world = World::from_config(config, seed);
+            world.randomize_from_seed(seed);
+            let path = std::path::Path::new(&settings.out_dir).join(format!("seed_{}.gif", seed));
+            let caption_seed = if settings.caption { Some(seed) } else { None };
+            let recorded = GifRecorder::create_upscaled(&path, config.size, &world.symbol_to_color, caption_seed, settings.gif_upscale).and_then(|mut recorder| {
+                for _ in 0..summary.active_frames {
+                    world.step();
+                    recorder.add_frame(&world.data)?;
+                }
+                Ok(())
+            });
+            match recorded {
+                Ok(()) => {
+                    saved += 1;
+                    search_stats.interesting.fetch_add(1, Ordering::Relaxed);
+                    println!("saved: {}  score: {}  ({} saved, {} explored)", path.display(), score, saved, explored);
+                    if settings.palette_sidecar {
+                        if let Err(e) = write_palette(path.with_extension("pal"), &world.symbol_to_color) {
+                            println!("# couldn't save palette for seed {}: {}", seed, e);
+                        }
+                    }
+                }
+                Err(e) => println!("# couldn't save gif for seed {}: {}", seed, e),
+            }
+        } else if explored % 100 == 0 {
+            println!("# {} saved, {} explored (up to seed {})", saved, explored, seed);
+        }
+        seed = seed.wrapping_add(1);
+    }
+    if saved == settings.max_gifs {
+        println!("# saved {} gifs, stopping (raise --max-gifs to keep going)", saved);
+    }
+}
+
+// everything a run can be configured with in a `--config-file` (values missing from the file keep their defaults)
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature="config-file", derive(Serialize, Deserialize), serde(default))]
+struct RunConfig {
+    world: WorldConfig,
+    novelty: NoveltySearchParams,
+    export: ExportOptions,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature="config-file", derive(Serialize, Deserialize), serde(default))]
+struct ExportOptions {
+    caption: bool,
+    palette_sidecar: bool,
+    antialias: bool,
+    thumbnail_dir: Option<String>,
+    thumbnail_samples: usize,
+    thumbnail_size: u32,
+    png_frames_dir: Option<String>,
+    png_upscale: u32,
+    gif_upscale: u32,
 }
 
-fn bool_vec_diff_count(vec1:&[bool], vec2:&[bool]) -> u32 {
-    let mut diff_count = 0;
-    for (i, v) in vec1.iter().enumerate() {
-        if vec2[i] != *v { diff_count += 1; }
+impl Default for ExportOptions {
+    fn default() -> ExportOptions {
+        ExportOptions { caption: false, palette_sidecar: false, antialias: true, thumbnail_dir: None, thumbnail_samples: 6, thumbnail_size: 128, png_frames_dir: None, png_upscale: 1, gif_upscale: 1 }
     }
-    diff_count
 }
 
-fn calculate_vec_hash(vec: &[u32]) -> u64 {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
+#[cfg(feature="config-file")]
+fn load_run_config(path:&str) -> Result<RunConfig, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    toml::from_str(&text).map_err(|e| e.to_string())
+}
+
+#[cfg(feature="config-file")]
+fn run_config_to_toml(config:&RunConfig) -> Result<String, String> {
+    toml::to_string_pretty(config).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature="config-file"))]
+fn load_run_config(_path:&str) -> Result<RunConfig, String> {
+    Err("config files need the `config-file` feature (`cargo run --features config-file`)".to_string())
+}
 
-    let mut s = DefaultHasher::new();
-    vec.hash(&mut s);
-    s.finish()
+#[cfg(not(feature="config-file"))]
+fn run_config_to_toml(_config:&RunConfig) -> Result<String, String> {
+    Err("config files need the `config-file` feature (`cargo run --features config-file`)".to_string())
 }
 
-fn make_gif_from_frames(frames: &[Vec<u32>], colors: &[(u8,u8,u8)], filename:&str) {
-    use gif::{Frame, Encoder, Repeat, SetParameter};
-    use std::fs::File;
-    use std::borrow::Cow;
-
-    let mut flat_colors: Vec<u8> = Vec::with_capacity(colors.len()*3);
-    for (r, g, b) in colors.iter() {
-        flat_colors.push(*r);
-        flat_colors.push(*g);
-        flat_colors.push(*b);
-    }
-    let width = (frames[0].len() as f32).sqrt() as u16;
-    let height = width;
-
-    let mut image = File::create(format!("./gifs/{}.gif", filename)).unwrap();
-    let mut encoder = Encoder::new(&mut image, width, height, &flat_colors[..]).unwrap();
-    encoder.set(Repeat::Infinite).unwrap();
-    for frame_data in frames {
-        let u8_frame_data: Vec<u8> = frame_data.iter().map(|x| *x as u8).collect();
-        let mut frame = Frame::default();
-        frame.width = width;
-        frame.height = height;
-        frame.buffer = Cow::Borrowed(&u8_frame_data[..]);
-        encoder.write_frame(&frame).unwrap();
+// the grid a seed's world starts from: --initial-grid if there is one, otherwise a sparse spark (--spark-density) or
+// dense noise, both seeded by `seed` so that the seed is all it takes to replay this exact run
+fn fill_starting_grid(world:&mut World, seed:u64, initial_grid_path:Option<&str>, spark_density:Option<f32>) {
+    match (initial_grid_path, spark_density) {
+        (Some(path), _) => world.load_grid(path).unwrap_or_else(|e| panic!("couldn't load --initial-grid {}: {}", path, e)),
+        (None, Some(density)) => world.randomize_weighted(&spark_weights(world.symbol_count, density), seed),
+        (None, None) => world.randomize_from_seed(seed),
     }
 }
 
+// `x,y`
+fn parse_cell(s:&str) -> Result<(u32, u32), String> {
+    let mut parts = s.split(',').map(|v| v.trim().parse::<u32>().map_err(|_| format!("{:?} isn't a cell coordinate", v)));
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(x), Some(y), None) => Ok((x?, y?)),
+        _ => Err(format!("{:?} should be two coordinates, like 10,20", s)),
+    }
+}
+
+// `a..b` (both ends included), or just `a` for a single value
+#[cfg(any(not(feature="interactive"), test))]
+fn parse_sweep_range(s:&str) -> Result<(f64, f64), String> {
+    let parse = |v:&str| v.trim().parse::<f64>().map_err(|_| format!("{:?} isn't a number", v));
+    let (first, last) = match s.find("..") {
+        Some(i) => (parse(&s[..i])?, parse(&s[i+2..])?),
+        None => (parse(s)?, parse(s)?),
+    };
+    if first > last {
+        return Err(format!("{} is after {}", first, last));
+    }
+    Ok((first, last))
+}
+
+#[cfg(feature="interactive")]
+fn clamp_avg_symbols_per_rule(avg_symbols_per_rule:f32, symbol_count:u32) -> f32 {
+    avg_symbols_per_rule.max(0.5).min(symbol_count as f32 - 0.5)
+}
+
+// the palette that the C key started cycling from, and how many degrees it has turned since
+#[cfg(feature="interactive")]
+type HueCycle = (Vec<(u8, u8, u8)>, f32);
+
+fn command_line_arg_value(args:&[String], flag:&str) -> Option<String> {
+    let i = args.iter().position(|a| a == flag)?;
+    Some(args.get(i+1).unwrap_or_else(|| panic!("{} needs a value", flag)).clone())
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_world() {
-        //let mut w = World::new(4);
+    fn test_parse_known_seeds() {
+        let text = "906339142304154875\nunique: 400  cell_change_diff_count: 31  frames: 400  seed: 535477901851029657\n\n# not a seed\n";
+        let seeds = parse_known_seeds(text);
+        assert_eq!(seeds.len(), 2);
+        assert!(seeds.contains(&906339142304154875));
+        assert!(seeds.contains(&535477901851029657));
+    }
+
+    #[test]
+    fn test_parse_seed_list() {
+        let text = "seed: 0xC93F1D2E6C2A1FB\n906339142304154875\n\nunique: 400  seed: 5\n906339142304154875\n";
+        assert_eq!(parse_seed_list(text), vec![0xC93F1D2E6C2A1FB, 906339142304154875, 5]);
+    }
+
+    #[test]
+    fn test_parse_cell() {
+        assert_eq!(parse_cell("10,20"), Ok((10, 20)));
+        assert_eq!(parse_cell(" 3, 0"), Ok((3, 0)));
+        assert!(parse_cell("3").is_err());
+        assert!(parse_cell("1,2,3").is_err());
+        assert!(parse_cell("-1,2").is_err());
+    }
+
+    #[test]
+    fn test_parse_sweep_range() {
+        assert_eq!(parse_sweep_range("3..8"), Ok((3.0, 8.0)));
+        assert_eq!(parse_sweep_range("1.5..4"), Ok((1.5, 4.0)));
+        assert_eq!(parse_sweep_range("2"), Ok((2.0, 2.0)));
+        assert!(parse_sweep_range("8..3").is_err());
+        assert!(parse_sweep_range("3..").is_err());
+    }
+
+    #[test]
+    fn test_parse_seed() {
+        assert_eq!(parse_seed("906339142304154875"), Ok(906339142304154875));
+        assert_eq!(parse_seed("906_339_142_304_154_875"), Ok(906339142304154875));
+        assert_eq!(parse_seed("0xC93F1D2E6C2A1FB"), Ok(0xC93F1D2E6C2A1FB));
+        assert_eq!(parse_seed("0xc93f_1d2e_6c2a_1fb"), Ok(0xC93F1D2E6C2A1FB));
+        assert!(parse_seed("banana").is_err());
+        assert!(parse_seed("0x").is_err());
+        assert!(parse_seed("-5").is_err());
+        assert!(parse_seed("99999999999999999999999").is_err());
+    }
+
+    #[test]
+    fn test_parse_catalog() {
+        let entries = parse_catalog("# flying\n15882147986537655481 novel\n\n0xff static 13 6.5\n").unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].seed, entries[0].label.as_str(), entries[0].symbol_count), (15882147986537655481, "novel", None));
+        assert_eq!((entries[1].seed, entries[1].symbol_count, entries[1].avg_symbols_per_rule), (255, Some(13), Some(6.5)));
+        assert!(parse_catalog("123").is_err());
+        assert!(parse_catalog("banana novel").is_err());
+    }
+
+    #[test]
+    fn test_title_template() {
+        let parts = parse_title_template("seed {seed} at {frame}").unwrap();
+        assert_eq!(parts, vec![
+            TitlePart::Text("seed ".to_string()),
+            TitlePart::Field(TitleField::Seed),
+            TitlePart::Text(" at ".to_string()),
+            TitlePart::Field(TitleField::Frame),
+        ]);
+        assert_eq!(render_title(&parts, |field| format!("{:?}", field)), "seed Seed at Frame");
+        assert!(parse_title_template(DEFAULT_TITLE_TEMPLATE).is_ok());
+        assert!(parse_title_template(SEED_LIST_TITLE_TEMPLATE).is_ok());
+        assert!(parse_title_template("{frame} {fsp}").unwrap_err().contains("{fsp}"));
+        assert!(parse_title_template("{frame").is_err());
+    }
+
+    #[cfg(feature="config-file")]
+    #[test]
+    fn test_run_config_toml_round_trip() {
+        let mut config = RunConfig::default();
+        config.world.symbol_count = 13;
+        config.world.transition_mode = TransitionMode::Majority;
+        config.novelty.burn_in_frames = 50;
+        config.export.thumbnail_dir = Some("thumbnails".to_string());
+        let text = run_config_to_toml(&config).unwrap();
+        assert_eq!(toml::from_str::<RunConfig>(&text).unwrap(), config);
+
+        // anything missing keeps its default:
+        let partial: RunConfig = toml::from_str("[world]\nsymbol_count = 7\n").unwrap();
+        assert_eq!(partial.world, WorldConfig { symbol_count: 7, ..WorldConfig::default() });
+        assert_eq!(partial.novelty, NoveltySearchParams::default());
+        assert_eq!(partial.export, ExportOptions::default());
     }
 }